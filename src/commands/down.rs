@@ -0,0 +1,11 @@
+use super::find_project_directory;
+use crate::devcontainers::Devcontainer;
+use crate::error::Result;
+
+pub fn run(dir: &Option<String>, volumes: bool, images: bool) -> Result<()> {
+    let directory = find_project_directory(dir)?;
+    let devcontainer = Devcontainer::load(directory)?;
+    devcontainer.down(volumes, images)?;
+
+    Ok(())
+}
@@ -0,0 +1,12 @@
+use super::get_project_directory;
+use crate::devcontainers::Devcontainer;
+use crate::error::Result;
+
+pub fn run(dir: &Option<String>, command: &[String]) -> Result<()> {
+    let directory = get_project_directory(dir)?;
+    let mut devcontainer = Devcontainer::load(directory)?;
+    let code = devcontainer.exec(command.to_vec())?;
+
+    // Exit with the command's status, as if it had been run directly
+    std::process::exit(code);
+}
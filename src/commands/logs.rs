@@ -0,0 +1,20 @@
+use super::find_project_directory;
+use crate::devcontainers::Devcontainer;
+use crate::error::Result;
+use crate::provider::Logs;
+
+pub fn run(
+    dir: &Option<String>,
+    service: &Option<String>,
+    follow: bool,
+    since: &Option<String>,
+) -> Result<()> {
+    let directory = find_project_directory(dir)?;
+    let devcontainer = Devcontainer::load(directory)?;
+
+    devcontainer.logs(&Logs {
+        service: service.clone(),
+        follow,
+        since: since.clone(),
+    })
+}
@@ -1,2 +1,41 @@
+use std::path::Path;
+use std::path::PathBuf;
+
+pub mod down;
+pub mod exec;
+pub mod logs;
+pub mod read_configuration;
 pub mod rebuild;
 pub mod start;
+pub mod status;
+pub mod stop;
+pub mod up;
+pub mod validate;
+
+/// The project directory given on the command line, or the current one.
+pub fn get_project_directory(dir: &Option<String>) -> std::io::Result<PathBuf> {
+    if let Some(path) = dir {
+        let mut expanded = shellexpand::env(path)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e.to_string()))?;
+
+        Path::new(expanded.to_mut()).canonicalize()
+    } else {
+        std::env::current_dir()
+    }
+}
+
+/// Like `get_project_directory`, but also works from anywhere inside the
+/// project by looking for `.devcontainer/devcontainer.json` in the parents.
+pub fn find_project_directory(dir: &Option<String>) -> std::io::Result<PathBuf> {
+    let directory = get_project_directory(dir)?;
+
+    Ok(directory
+        .ancestors()
+        .find(|dir| {
+            dir.join(".devcontainer")
+                .join("devcontainer.json")
+                .is_file()
+        })
+        .map(Path::to_path_buf)
+        .unwrap_or(directory))
+}
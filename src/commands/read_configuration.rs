@@ -0,0 +1,13 @@
+use super::get_project_directory;
+use crate::devcontainers::Devcontainer;
+use crate::error::Result;
+
+pub fn run(dir: &Option<String>) -> Result<()> {
+    let directory = get_project_directory(dir)?;
+    let mut devcontainer = Devcontainer::load(directory)?;
+    let configuration = devcontainer.read_configuration()?;
+
+    println!("{}", serde_json::to_string_pretty(&configuration)?);
+
+    Ok(())
+}
@@ -1,22 +1,11 @@
-use std::path::Path;
-use std::path::PathBuf;
-
+use super::get_project_directory;
 use crate::devcontainers::Devcontainer;
+use crate::error::Result;
 
-pub fn run(dir: &Option<String>) -> std::io::Result<()> {
+pub fn run(dir: &Option<String>) -> Result<()> {
     let directory = get_project_directory(dir)?;
-    let devcontainer = Devcontainer::load(directory);
+    let mut devcontainer = Devcontainer::load(directory)?;
     devcontainer.run(true)?;
 
     Ok(())
 }
-
-fn get_project_directory(dir: &Option<String>) -> std::io::Result<PathBuf> {
-    if let Some(path) = dir {
-        let mut expanded = shellexpand::env(path).expect("Could not expand dir");
-
-        Path::new(expanded.to_mut()).canonicalize()
-    } else {
-        std::env::current_dir()
-    }
-}
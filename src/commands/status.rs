@@ -0,0 +1,66 @@
+use crate::devcontainers::status;
+use crate::error::Result;
+
+pub fn run(json: bool) -> Result<()> {
+    let statuses = status::list()?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&statuses)?);
+        return Ok(());
+    }
+
+    if statuses.is_empty() {
+        println!("No devcon containers found");
+        return Ok(());
+    }
+
+    let mut rows = vec![[
+        "PROJECT", "NAME", "PROVIDER", "STATE", "UPTIME", "PORTS", "DRIFTED",
+    ]
+    .map(|header| header.to_string())];
+
+    for status in statuses {
+        rows.push([
+            status.project,
+            status.name.unwrap_or_else(|| "-".to_string()),
+            status.provider.unwrap_or_else(|| "-".to_string()),
+            status.state,
+            status
+                .uptime
+                .map(status::duration)
+                .unwrap_or_else(|| "-".to_string()),
+            if status.ports.is_empty() {
+                "-".to_string()
+            } else {
+                status.ports.join(", ")
+            },
+            match status.drifted {
+                Some(true) => "yes",
+                Some(false) => "no",
+                None => "unknown",
+            }
+            .to_string(),
+        ]);
+    }
+
+    // Pads every column but the last to its widest value
+    let widths: Vec<usize> = (0..rows[0].len())
+        .map(|column| {
+            rows.iter()
+                .map(|row| row[column].chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    for row in rows {
+        let line: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .map(|(value, width)| format!("{:<width$}", value, width = width))
+            .collect();
+        println!("{}", line.join("  ").trim_end());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,11 @@
+use super::find_project_directory;
+use crate::devcontainers::Devcontainer;
+use crate::error::Result;
+
+pub fn run(dir: &Option<String>) -> Result<()> {
+    let directory = find_project_directory(dir)?;
+    let devcontainer = Devcontainer::load(directory)?;
+    devcontainer.stop()?;
+
+    Ok(())
+}
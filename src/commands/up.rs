@@ -0,0 +1,38 @@
+use super::get_project_directory;
+use crate::devcontainers::Devcontainer;
+use crate::error::Result;
+use crate::provider;
+
+pub fn run(dir: &Option<String>, use_cache: bool, json: bool) -> Result<()> {
+    // Engine output goes to stderr, so stdout only holds the result
+    provider::set_json(json);
+
+    let mut reported = false;
+    let result = up(dir, use_cache, |output| {
+        if json {
+            println!("{}", output);
+        }
+        reported = true;
+    });
+
+    // Failures after the container was reported as ready only set the exit
+    // code
+    if json && !reported {
+        if let Err(error) = &result {
+            let output = serde_json::json!({
+                "outcome": "error",
+                "message": error.to_string(),
+            });
+            println!("{}", output);
+        }
+    }
+
+    result
+}
+
+fn up(dir: &Option<String>, use_cache: bool, report: impl FnOnce(serde_json::Value)) -> Result<()> {
+    let directory = get_project_directory(dir)?;
+    let mut devcontainer = Devcontainer::load(directory)?;
+
+    devcontainer.up(use_cache, report)
+}
@@ -0,0 +1,43 @@
+use colored::Colorize;
+
+use super::get_project_directory;
+use crate::devcontainers::config::Config;
+use crate::devcontainers::schema;
+use crate::devcontainers::schema::Issue;
+use crate::error::DevconError;
+use crate::error::Result;
+
+pub fn run(dir: &Option<String>) -> Result<()> {
+    let directory = get_project_directory(dir)?;
+    let file = directory.join(".devcontainer").join("devcontainer.json");
+
+    // The schema allows some values devcon can't read yet, so both are
+    // checked
+    let parsed = Config::parse(&file);
+    let mut issues = schema::validate_file(&file)?;
+
+    // Valid by the spec, but devcon only builds from a Dockerfile or compose
+    if let Ok(config) = &parsed {
+        if !config.is_compose() && config.dockerfile().is_none() {
+            issues.push(Issue {
+                path: String::new(),
+                message: "devcon needs build.dockerfile or dockerComposeFile".to_string(),
+            });
+        }
+    }
+    for issue in &issues {
+        println!("{} {}", "Problem:".yellow().bold(), issue);
+    }
+
+    parsed?;
+    if issues.is_empty() {
+        println!("{} is valid", file.display());
+        return Ok(());
+    }
+
+    Err(DevconError::InvalidConfig(format!(
+        "{} has {} problem(s)",
+        file.display(),
+        issues.len()
+    )))
+}
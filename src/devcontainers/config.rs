@@ -1,42 +1,154 @@
 use serde::Deserialize;
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::path::Path;
 
-#[derive(Debug, Deserialize, Clone)]
-enum ShutdownAction {
+use super::metadata::ForwardPort;
+use super::metadata::LifecycleCommand;
+use super::metadata::Metadata;
+use super::metadata::Mount;
+use super::variables::Variables;
+use crate::error::DevconError;
+use crate::error::ParseError;
+
+/// How much of the configured name is kept in container names.
+const MAX_NAME_LENGTH: usize = 32;
+
+/// The properties devcon reads, used to suggest fixes for misspellings.
+const PROPERTIES: &[&str] = &[
+    "name",
+    "build",
+    "forwardPorts",
+    "onCreateCommand",
+    "updateContentCommand",
+    "postCreateCommand",
+    "remoteUser",
+    "runArgs",
+    "remoteEnv",
+    "containerEnv",
+    "mounts",
+    "capAdd",
+    "securityOpt",
+    "containerUser",
+    "overrideCommand",
+    "dockerComposeFile",
+    "service",
+    "runServices",
+    "workspaceFolder",
+    "shutdownAction",
+    "userEnvProbe",
+    "waitFor",
+    "customizations",
+];
+
+const BUILD_PROPERTIES: &[&str] = &["dockerfile", "args"];
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum ShutdownAction {
     None,
     StopContainer,
     StopCompose,
 }
 
-impl Default for ShutdownAction {
-    fn default() -> Self {
-        Self::StopContainer
+/// The last lifecycle stage `devcon up` waits for, in the order they run.
+#[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq, PartialOrd)]
+pub enum WaitFor {
+    #[serde(rename = "initializeCommand")]
+    Initialize,
+    #[serde(rename = "onCreateCommand")]
+    OnCreate,
+    #[default]
+    #[serde(rename = "updateContentCommand")]
+    UpdateContent,
+    #[serde(rename = "postCreateCommand")]
+    PostCreate,
+    #[serde(rename = "postStartCommand")]
+    PostStart,
+}
+
+/// The shell used to read the user's environment before running commands.
+#[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum UserEnvProbe {
+    None,
+    LoginShell,
+    #[default]
+    LoginInteractiveShell,
+    InteractiveShell,
+}
+
+impl UserEnvProbe {
+    /// The shell flags for the probe, or `None` when it is off.
+    pub fn flags(&self) -> Option<&'static str> {
+        match self {
+            Self::None => None,
+            Self::LoginShell => Some("-lc"),
+            Self::LoginInteractiveShell => Some("-lic"),
+            Self::InteractiveShell => Some("-ic"),
+        }
     }
 }
 
 #[derive(Debug, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct Config {
+    #[serde(default)]
     name: String,
     pub build: Option<Build>,
     #[serde(default)]
-    pub forward_ports: Vec<u16>,
-    pub on_create_command: Option<String>,
-    pub update_content_command: Option<String>,
-    pub post_create_command: Option<String>,
-    #[serde(default = "default_remote_user")]
-    pub remote_user: String,
+    pub forward_ports: Vec<ForwardPort>,
+    pub on_create_command: Option<LifecycleCommand>,
+    pub update_content_command: Option<LifecycleCommand>,
+    pub post_create_command: Option<LifecycleCommand>,
+    pub remote_user: Option<String>,
     #[serde(default)]
     pub run_args: Vec<String>,
     #[serde(default)]
-    pub remote_env: HashMap<String, String>,
-    pub docker_compose_file: Option<String>,
+    pub remote_env: HashMap<String, Option<String>>,
+    #[serde(default)]
+    pub container_env: HashMap<String, String>,
+    #[serde(default)]
+    pub mounts: Vec<Mount>,
+    #[serde(default)]
+    pub cap_add: Vec<String>,
+    #[serde(default)]
+    pub security_opt: Vec<String>,
+    pub container_user: Option<String>,
+    pub override_command: Option<bool>,
+    pub docker_compose_file: Option<ComposeFiles>,
     pub service: Option<String>,
+    pub run_services: Option<Vec<String>>,
     #[serde(default = "default_workspace_folder")]
     pub workspace_folder: String,
+    shutdown_action: Option<ShutdownAction>,
+    #[serde(default)]
+    pub user_env_probe: UserEnvProbe,
+    #[serde(default)]
+    pub wait_for: WaitFor,
+    #[serde(default)]
+    pub customizations: Customizations,
+}
+
+#[derive(Debug, Default, Deserialize, Clone)]
+pub struct Customizations {
     #[serde(default)]
-    shutdown_action: ShutdownAction,
+    pub devcon: DevconCustomizations,
+}
+
+/// Settings a project can set for devcon under `customizations.devcon`,
+/// taking precedence over the global settings.
+#[derive(Debug, Default, Deserialize, Clone)]
+pub struct DevconCustomizations {
+    pub context: Option<String>,
+    pub host: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(untagged)]
+pub enum ComposeFiles {
+    File(String),
+    Files(Vec<String>),
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -48,11 +160,62 @@ pub struct Build {
 }
 
 impl Config {
-    pub fn parse(file: &Path) -> Result<Config, std::io::Error> {
+    pub fn parse(file: &Path) -> Result<Config, DevconError> {
+        Ok(serde_json::from_value(Self::parse_value(file)?)?)
+    }
+
+    /// Reads devcontainer.json with its variables substituted.
+    pub fn parse_value(file: &Path) -> Result<serde_json::Value, DevconError> {
+        Self::substituted(file, true)
+    }
+
+    /// The metadata entry written to the image label, marked as devcon's. The
+    /// local environment is left unsubstituted, so secrets passed in through
+    /// it stay out of the image.
+    pub fn label_metadata(file: &Path) -> Result<Metadata, DevconError> {
+        let config: Config = serde_json::from_value(Self::substituted(file, false)?)?;
+
+        Ok(Metadata {
+            devcon_config: true,
+            ..config.metadata()
+        })
+    }
+
+    fn substituted(file: &Path, env: bool) -> Result<serde_json::Value, DevconError> {
+        if !file.is_file() {
+            return Err(DevconError::ConfigNotFound(file.to_path_buf()));
+        }
+
+        // Parsed into a config first, so type errors have a location
         let contents = std::fs::read_to_string(file)?;
-        let config: Config = json5::from_str(&contents).unwrap();
+        let config: Config =
+            json5::from_str(&contents).map_err(|json5::Error::Message { msg, location }| {
+                let line = location.as_ref().map(|l| l.line);
+                let column = location.as_ref().map(|l| l.column);
+
+                DevconError::ConfigParse(Box::new(ParseError {
+                    file: file.to_path_buf(),
+                    line,
+                    column,
+                    message: error_message(&msg),
+                    snippet: line.zip(column).and_then(|(l, c)| snippet(&contents, l, c)),
+                    hints: hints(&contents),
+                }))
+            })?;
 
-        Ok(config)
+        let mut value: serde_json::Value = json5::from_str(&contents)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+
+        // The workspace folder may use the local variables itself
+        let directory = file.parent().and_then(|p| p.parent()).unwrap_or(file);
+        let workspace_folder = Variables::new(directory, "").replace(&config.workspace_folder);
+        let variables = Variables {
+            env,
+            ..Variables::new(directory, &workspace_folder)
+        };
+        variables.substitute(&mut value);
+
+        Ok(value)
     }
 
     pub fn dockerfile(&self) -> Option<String> {
@@ -63,24 +226,221 @@ impl Config {
         self.build.clone().map(|b| b.args).unwrap_or_default()
     }
 
-    pub fn safe_name(&self) -> String {
-        let name = self
-            .name
-            .to_lowercase()
-            .replace(' ', "-")
-            .trim()
-            .to_string();
+    /// A container name unique to the workspace, so checkouts of the same
+    /// project don't share a container. Only characters every engine accepts
+    /// in container, image and compose project names are kept.
+    pub fn safe_name(&self, directory: &Path) -> String {
+        let mut name = String::new();
+        for c in self.name.to_lowercase().chars() {
+            if c.is_ascii_alphanumeric() {
+                name.push(c);
+            } else if !name.is_empty() && !name.ends_with('-') {
+                name.push('-');
+            }
+        }
+        name.truncate(MAX_NAME_LENGTH);
+        let name = name.trim_end_matches('-');
 
-        format!("devcon-{}", name)
+        let hash = super::hash(directory.to_string_lossy().as_bytes());
+        if name.is_empty() {
+            format!("devcon-{}", &hash[..8])
+        } else {
+            format!("devcon-{}-{}", name, &hash[..8])
+        }
     }
 
-    pub fn should_shutdown(&self) -> bool {
-        !matches!(self.shutdown_action, ShutdownAction::None)
+    /// Defaults to stopping the whole project for compose setups and just
+    /// the container otherwise, as in the spec.
+    pub fn shutdown_action(&self) -> ShutdownAction {
+        match self.shutdown_action {
+            Some(action) => action,
+            None if self.is_compose() => ShutdownAction::StopCompose,
+            None => ShutdownAction::StopContainer,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
     }
 
     pub fn is_compose(&self) -> bool {
         self.docker_compose_file.is_some()
     }
+
+    /// The compose files in the order they are layered.
+    pub fn compose_files(&self) -> Vec<String> {
+        match &self.docker_compose_file {
+            Some(ComposeFiles::File(file)) => vec![file.clone()],
+            Some(ComposeFiles::Files(files)) => files.clone(),
+            None => vec![],
+        }
+    }
+
+    /// The services to start, always including the primary service. Empty
+    /// means every service in the compose files.
+    pub fn run_services(&self) -> Vec<String> {
+        match (&self.run_services, &self.service) {
+            (Some(services), Some(service)) => {
+                let mut services = services.clone();
+                if !services.contains(service) {
+                    services.push(service.clone());
+                }
+
+                services
+            }
+            (Some(services), None) => services.clone(),
+            (None, _) => vec![],
+        }
+    }
+
+    /// The env every process in the container gets, with remoteEnv applied
+    /// on top of containerEnv.
+    pub fn env(&self) -> HashMap<String, String> {
+        let mut env = self.container_env.clone();
+        for (key, value) in &self.remote_env {
+            match value {
+                Some(value) => env.insert(key.clone(), value.clone()),
+                None => env.remove(key),
+            };
+        }
+
+        env
+    }
+
+    /// The forwarded port numbers on the container itself.
+    pub fn container_ports(&self) -> Vec<u16> {
+        self.forward_ports
+            .iter()
+            .filter(|port| port.service().is_none())
+            .filter_map(ForwardPort::port)
+            .collect()
+    }
+
+    /// The forwarded port numbers by the compose service they are on. Ports
+    /// that don't name one are on the primary service.
+    pub fn service_ports(&self) -> BTreeMap<String, Vec<u16>> {
+        let mut ports: BTreeMap<String, Vec<u16>> = BTreeMap::new();
+        for port in &self.forward_ports {
+            let service = port.service().or(self.service.as_deref());
+            if let (Some(service), Some(number)) = (service, port.port()) {
+                ports.entry(service.to_string()).or_default().push(number);
+            }
+        }
+
+        ports
+    }
+
+    pub fn remote_user(&self) -> String {
+        self.remote_user.clone().unwrap_or_else(default_remote_user)
+    }
+
+    /// The image metadata entry contributed by this devcontainer.json.
+    pub fn metadata(&self) -> Metadata {
+        Metadata {
+            remote_user: self.remote_user.clone(),
+            on_create_command: self.on_create_command.clone(),
+            update_content_command: self.update_content_command.clone(),
+            post_create_command: self.post_create_command.clone(),
+            remote_env: self.remote_env.clone().into_iter().collect(),
+            container_env: self.container_env.clone().into_iter().collect(),
+            mounts: self.mounts.clone(),
+            forward_ports: self.forward_ports.clone(),
+            devcon_config: false,
+        }
+    }
+}
+
+/// Syntax errors come with pest's own rendering of the source, keep only
+/// the explanation from it.
+fn error_message(message: &str) -> String {
+    message
+        .lines()
+        .find_map(|line| line.trim_start().strip_prefix("= "))
+        .unwrap_or(message)
+        .to_string()
+}
+
+/// The offending line with a marker under the column.
+fn snippet(contents: &str, line: usize, column: usize) -> Option<String> {
+    let source = contents.lines().nth(line.checked_sub(1)?)?;
+    let gutter = " ".repeat(line.to_string().len());
+
+    Some(format!(
+        "{} |\n{} | {}\n{} | {}^",
+        gutter,
+        line,
+        source,
+        gutter,
+        " ".repeat(column.saturating_sub(1))
+    ))
+}
+
+/// Suggestions for properties that look like misspellings of ones devcon
+/// knows. Only works when the file is valid JSON5.
+fn hints(contents: &str) -> Vec<String> {
+    let value: serde_json::Value = match json5::from_str(contents) {
+        Ok(value) => value,
+        Err(_) => return vec![],
+    };
+
+    let mut hints = vec![];
+    let mut check = |object: &serde_json::Value, known: &[&str], prefix: &str| {
+        for key in object.as_object().into_iter().flat_map(|o| o.keys()) {
+            if known.contains(&key.as_str()) {
+                continue;
+            }
+
+            if let Some(suggestion) = closest(key, known) {
+                hints.push(format!(
+                    "did you mean `{}{}` instead of `{}{}`?",
+                    prefix, suggestion, prefix, key
+                ));
+            }
+        }
+    };
+
+    check(&value, PROPERTIES, "");
+    check(&value["build"], BUILD_PROPERTIES, "build.");
+
+    hints
+}
+
+/// The known name closest to `name`, if it is close enough to be a typo.
+pub(crate) fn closest<'a>(name: &str, known: &[&'a str]) -> Option<&'a str> {
+    let name = name.to_lowercase();
+
+    known
+        .iter()
+        .map(|candidate| (distance(&name, &candidate.to_lowercase()), *candidate))
+        .filter(|(distance, candidate)| *distance <= 2 && *distance < candidate.len() / 2)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// The edit distance between two strings, counting a swap of neighbouring
+/// characters as one edit.
+fn distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut rows = vec![(0..=b.len()).collect::<Vec<usize>>()];
+
+    for i in 1..=a.len() {
+        let mut row = vec![i];
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            let mut best = (rows[i - 1][j - 1] + cost)
+                .min(rows[i - 1][j] + 1)
+                .min(row[j - 1] + 1);
+
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                best = best.min(rows[i - 2][j - 2] + 1);
+            }
+            row.push(best);
+        }
+        rows.push(row);
+    }
+
+    rows[a.len()][b.len()]
 }
 
 fn default_remote_user() -> String {
@@ -90,3 +450,52 @@ fn default_remote_user() -> String {
 fn default_workspace_folder() -> String {
     "/workspace".to_string()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Config;
+    use crate::devcontainers::schema;
+
+    #[test]
+    fn parses_what_the_schema_allows() {
+        let value = serde_json::json!({
+            "forwardPorts": [8080, "3000", "db:5432"],
+            "remoteEnv": { "A": "1", "B": null },
+            "mounts": [{ "type": "volume", "target": "/cache" }],
+            "customizations": { "devcon": { "host": "ssh://box" }, "vscode": {} },
+            "secrets": { "TOKEN": { "description": "An API token" } },
+        });
+        assert!(schema::validate(&value).is_empty());
+
+        let config: Config = serde_json::from_value(value).unwrap();
+        assert_eq!(config.container_ports(), vec![8080, 3000]);
+        assert_eq!(config.env().get("A").map(|a| a.as_str()), Some("1"));
+        assert!(!config.env().contains_key("B"));
+    }
+
+    fn safe_name(name: &str) -> String {
+        let config: Config = serde_json::from_value(serde_json::json!({ "name": name })).unwrap();
+        let name = config.safe_name(std::path::Path::new("/home/me/project"));
+
+        // The hash of the directory is always appended
+        let (name, hash) = name.rsplit_once('-').unwrap();
+        assert_eq!(hash.len(), 8);
+        name.to_string()
+    }
+
+    #[test]
+    fn keeps_names_engines_accept() {
+        assert_eq!(safe_name("My Project"), "devcon-my-project");
+        assert_eq!(safe_name("Café – Ünïcode"), "devcon-caf-n-code");
+        assert_eq!(safe_name("日本語"), "devcon");
+        assert_eq!(safe_name("!!! ... ???"), "devcon");
+        assert_eq!(safe_name("--a__b--"), "devcon-a-b");
+
+        let long = safe_name("a very long project name that keeps going on");
+        assert_eq!(long, "devcon-a-very-long-project-name-that-ke");
+        assert_eq!(
+            safe_name(&format!("{}-tail", "x".repeat(31))),
+            format!("devcon-{}", "x".repeat(31))
+        );
+    }
+}
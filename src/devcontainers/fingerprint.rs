@@ -0,0 +1,152 @@
+use sha2::Digest;
+use sha2::Sha256;
+use std::io::Result;
+use std::path::Path;
+
+use super::config::Config;
+use crate::provider::compose_file;
+use crate::provider::dockerignore::DockerIgnore;
+
+/// Hashes everything a container is built from: devcontainer.json, the
+/// compose files, the Dockerfiles and the files they copy in. A container
+/// labelled with a different hash was built from older inputs.
+pub fn inputs(directory: &Path, file: &Path, config: &Config, project: &str) -> Result<String> {
+    let mut hasher = Sha256::new();
+    add_file(&mut hasher, file)?;
+
+    if config.is_compose() {
+        let files = super::compose_files(directory, config);
+        for file in &files {
+            add_file(&mut hasher, Path::new(file))?;
+        }
+
+        for service in compose_file::load(&files, project)? {
+            if let Some(build) = service.build {
+                let dockerfile = build.dockerfile.as_deref().unwrap_or("Dockerfile");
+                add_dockerfile(&mut hasher, &build.context.join(dockerfile), &build.context)?;
+            }
+        }
+    } else if let Some(dockerfile) = config.dockerfile() {
+        let dockerfile = directory.join(".devcontainer").join(dockerfile);
+        add_dockerfile(&mut hasher, &dockerfile, directory)?;
+    }
+
+    Ok(super::hex(&hasher.finalize()))
+}
+
+/// Adds a Dockerfile and the local sources of its `COPY` and `ADD`
+/// instructions.
+fn add_dockerfile(hasher: &mut Sha256, dockerfile: &Path, context: &Path) -> Result<()> {
+    if !dockerfile.is_file() {
+        return Ok(());
+    }
+
+    add_file(hasher, dockerfile)?;
+
+    let ignore = DockerIgnore::read(context);
+    let contents = std::fs::read_to_string(dockerfile)?;
+    for source in copy_sources(&contents) {
+        let source = source.trim_start_matches('/');
+        add_path(hasher, context, Path::new(source), &ignore)?;
+    }
+
+    Ok(())
+}
+
+/// The sources of every `COPY` and `ADD` that reads from the build context.
+/// A glob is widened to the directory it matches in.
+fn copy_sources(dockerfile: &str) -> Vec<String> {
+    let mut sources = vec![];
+
+    for line in dockerfile.lines() {
+        let mut words = line.split_whitespace();
+        let instruction = words.next().unwrap_or_default().to_uppercase();
+        if instruction != "COPY" && instruction != "ADD" {
+            continue;
+        }
+
+        let rest: Vec<&str> = words.collect();
+
+        // Copies from another stage or image don't read the context
+        if rest.iter().any(|word| word.starts_with("--from")) {
+            continue;
+        }
+
+        let args: Vec<String> = if rest.first().is_some_and(|word| word.starts_with('[')) {
+            serde_json::from_str(&rest.join(" ")).unwrap_or_default()
+        } else {
+            rest.iter()
+                .filter(|word| !word.starts_with("--"))
+                .map(|word| word.to_string())
+                .collect()
+        };
+
+        // The last argument is the destination
+        for source in args.iter().take(args.len().saturating_sub(1)) {
+            if source.contains("://") {
+                continue;
+            }
+
+            let source = match source.find(['*', '?', '[']) {
+                Some(index) => match source[..index].rfind('/') {
+                    Some(slash) => source[..slash].to_string(),
+                    None => ".".to_string(),
+                },
+                None => source.clone(),
+            };
+            sources.push(source);
+        }
+    }
+
+    sources
+}
+
+/// Adds a file, or every file under a directory in a stable order, unless
+/// .dockerignore leaves it out of the context. `.git` is skipped too, as
+/// it changes with every commit.
+fn add_path(
+    hasher: &mut Sha256,
+    context: &Path,
+    relative: &Path,
+    ignore: &DockerIgnore,
+) -> Result<()> {
+    if relative.file_name().is_some_and(|name| name == ".git") {
+        return Ok(());
+    }
+
+    let path = context.join(relative);
+    let ignored = ignore.is_ignored(&relative.to_string_lossy());
+
+    if path.is_file() {
+        return if ignored {
+            Ok(())
+        } else {
+            add_file(hasher, &path)
+        };
+    }
+
+    // Exceptions can still include files under an ignored directory
+    if !path.is_dir() || (ignored && !ignore.has_exceptions()) {
+        return Ok(());
+    }
+
+    let mut entries: Vec<_> = std::fs::read_dir(&path)?
+        .map(|entry| entry.map(|entry| relative.join(entry.file_name())))
+        .collect::<Result<_>>()?;
+    entries.sort();
+
+    for entry in entries {
+        if !context.join(&entry).is_symlink() {
+            add_path(hasher, context, &entry, ignore)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn add_file(hasher: &mut Sha256, file: &Path) -> Result<()> {
+    hasher.update(file.to_string_lossy().as_bytes());
+    hasher.update(std::fs::read(file)?);
+
+    Ok(())
+}
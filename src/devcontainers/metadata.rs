@@ -0,0 +1,328 @@
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+
+/// A lifecycle hook as allowed by the spec: a shell string, an argv array, or
+/// a map of named commands.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum LifecycleCommand {
+    Shell(String),
+    Exec(Vec<String>),
+    Parallel(BTreeMap<String, LifecycleCommand>),
+}
+
+impl LifecycleCommand {
+    /// Returns the shell commands to run for this hook.
+    pub fn commands(&self) -> Vec<String> {
+        match self {
+            Self::Shell(command) => vec![command.clone()],
+            Self::Exec(args) => vec![args
+                .iter()
+                .map(|a| shell_quote(a))
+                .collect::<Vec<_>>()
+                .join(" ")],
+            Self::Parallel(commands) => commands.values().flat_map(|c| c.commands()).collect(),
+        }
+    }
+}
+
+/// A mount as either a `--mount` string or its object form.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum Mount {
+    Spec(String),
+    Object {
+        #[serde(rename = "type")]
+        kind: String,
+        source: Option<String>,
+        target: String,
+    },
+}
+
+impl Mount {
+    pub fn target(&self) -> Option<String> {
+        match self {
+            Self::Spec(spec) => spec.split(',').find_map(|part| {
+                let (key, value) = part.split_once('=')?;
+                matches!(key.trim(), "target" | "destination" | "dst").then(|| value.to_string())
+            }),
+            Self::Object { target, .. } => Some(target.clone()),
+        }
+    }
+}
+
+impl std::fmt::Display for Mount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Spec(spec) => write!(f, "{}", spec),
+            Self::Object {
+                kind,
+                source: Some(source),
+                target,
+            } => write!(f, "type={},source={},target={}", kind, source, target),
+            Self::Object { kind, target, .. } => write!(f, "type={},target={}", kind, target),
+        }
+    }
+}
+
+/// A forwarded port, either a number or a `"host:port"` string whose host
+/// names the compose service the port is on.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum ForwardPort {
+    Number(u16),
+    Address(String),
+}
+
+impl ForwardPort {
+    /// The service the port is on, `None` for the primary container.
+    pub fn service(&self) -> Option<&str> {
+        match self {
+            Self::Number(_) => None,
+            Self::Address(address) => address
+                .split_once(':')
+                .map(|(host, _)| host)
+                .filter(|host| !matches!(*host, "" | "localhost" | "127.0.0.1")),
+        }
+    }
+
+    /// The port number, `None` for strings that don't end in one.
+    pub fn port(&self) -> Option<u16> {
+        match self {
+            Self::Number(port) => Some(*port),
+            Self::Address(address) => address.rsplit(':').next()?.parse().ok(),
+        }
+    }
+}
+
+/// One entry of the `devcontainer.metadata` image label.
+#[derive(Debug, Default, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Metadata {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remote_user: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_create_command: Option<LifecycleCommand>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub update_content_command: Option<LifecycleCommand>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post_create_command: Option<LifecycleCommand>,
+    /// A `null` value unsets the variable.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub remote_env: BTreeMap<String, Option<String>>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub container_env: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mounts: Vec<Mount>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub forward_ports: Vec<ForwardPort>,
+    /// Marks the entry devcon wrote for devcontainer.json, which the current
+    /// devcontainer.json replaces rather than merges with.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub devcon_config: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Label {
+    Entries(Vec<Metadata>),
+    Entry(Box<Metadata>),
+}
+
+/// The result of merging image metadata with devcontainer.json.
+#[derive(Debug, Default, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergedMetadata {
+    pub remote_user: Option<String>,
+    pub on_create_commands: Vec<LifecycleCommand>,
+    pub update_content_commands: Vec<LifecycleCommand>,
+    pub post_create_commands: Vec<LifecycleCommand>,
+    pub remote_env: HashMap<String, String>,
+    pub container_env: HashMap<String, String>,
+    pub mounts: Vec<Mount>,
+    pub forward_ports: Vec<ForwardPort>,
+}
+
+impl Metadata {
+    /// Parses a `devcontainer.metadata` label, which is either an array of
+    /// entries or a single entry.
+    pub fn parse_label(label: &str) -> serde_json::Result<Vec<Metadata>> {
+        match serde_json::from_str(label)? {
+            Label::Entries(entries) => Ok(entries),
+            Label::Entry(entry) => Ok(vec![*entry]),
+        }
+    }
+
+    /// Appends the devcontainer.json entry to the image entries. Images built
+    /// by devcon already carry an entry for an earlier devcontainer.json,
+    /// which is replaced, so edits don't merge with what they changed.
+    pub fn entries(image: &[Metadata], config: Metadata) -> Vec<Metadata> {
+        let mut entries: Vec<Metadata> = image
+            .iter()
+            .filter(|entry| !entry.devcon_config)
+            .cloned()
+            .collect();
+        entries.push(config);
+
+        entries
+    }
+
+    /// Merges entries in order using the spec's rules: single values are
+    /// last-wins, env maps are merged per variable, mounts are merged per
+    /// target, ports are unioned and lifecycle hooks are all kept.
+    pub fn merge(entries: &[Metadata]) -> MergedMetadata {
+        let mut merged = MergedMetadata::default();
+
+        for entry in entries {
+            if let Some(user) = &entry.remote_user {
+                merged.remote_user = Some(user.clone());
+            }
+
+            merged
+                .on_create_commands
+                .extend(entry.on_create_command.clone());
+            merged
+                .update_content_commands
+                .extend(entry.update_content_command.clone());
+            merged
+                .post_create_commands
+                .extend(entry.post_create_command.clone());
+
+            for (key, value) in &entry.remote_env {
+                match value {
+                    Some(value) => merged.remote_env.insert(key.clone(), value.clone()),
+                    None => merged.remote_env.remove(key),
+                };
+            }
+            merged.container_env.extend(entry.container_env.clone());
+
+            for mount in &entry.mounts {
+                let target = mount.target();
+                merged
+                    .mounts
+                    .retain(|m| target.is_none() || m.target() != target);
+                merged.mounts.push(mount.clone());
+            }
+
+            for port in &entry.forward_ports {
+                let key = (port.service(), port.port());
+                if !merged
+                    .forward_ports
+                    .iter()
+                    .any(|p| (p.service(), p.port()) == key)
+                {
+                    merged.forward_ports.push(port.clone());
+                }
+            }
+        }
+
+        merged
+    }
+}
+
+fn shell_quote(arg: &str) -> String {
+    let safe = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./=:@%+,".contains(c));
+
+    if safe {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ForwardPort;
+    use super::LifecycleCommand;
+    use super::Metadata;
+    use super::Mount;
+
+    fn shell(command: &str) -> Option<LifecycleCommand> {
+        Some(LifecycleCommand::Shell(command.to_string()))
+    }
+
+    #[test]
+    fn replaces_the_entry_devcon_wrote() {
+        let base = Metadata {
+            remote_user: Some("vscode".to_string()),
+            post_create_command: shell("base"),
+            ..Default::default()
+        };
+        let written = Metadata {
+            post_create_command: shell("old"),
+            remote_env: [("OLD".to_string(), Some("1".to_string()))].into(),
+            devcon_config: true,
+            ..Default::default()
+        };
+        let config = Metadata {
+            post_create_command: shell("new"),
+            ..Default::default()
+        };
+
+        let entries = Metadata::entries(&[base.clone(), written], config.clone());
+        assert_eq!(entries, vec![base, config]);
+
+        let merged = Metadata::merge(&entries);
+        assert_eq!(
+            merged.post_create_commands,
+            vec![shell("base").unwrap(), shell("new").unwrap()]
+        );
+        assert!(merged.remote_env.is_empty());
+    }
+
+    #[test]
+    fn merges_by_the_spec_rules() {
+        let mount = |source: &str, target: &str| {
+            Mount::Spec(format!("source={},target={}", source, target))
+        };
+        let image = Metadata {
+            remote_user: Some("vscode".to_string()),
+            on_create_command: shell("image"),
+            remote_env: [
+                ("KEPT".to_string(), Some("image".to_string())),
+                ("UNSET".to_string(), Some("image".to_string())),
+            ]
+            .into(),
+            mounts: vec![mount("a", "/cache"), mount("b", "/data")],
+            forward_ports: vec![ForwardPort::Number(3000)],
+            ..Default::default()
+        };
+        let config = Metadata {
+            remote_user: Some("dev".to_string()),
+            on_create_command: shell("config"),
+            remote_env: [("UNSET".to_string(), None)].into(),
+            mounts: vec![mount("c", "/cache")],
+            forward_ports: vec![
+                ForwardPort::Address("3000".to_string()),
+                ForwardPort::Address("db:5432".to_string()),
+            ],
+            ..Default::default()
+        };
+
+        let merged = Metadata::merge(&[image, config]);
+
+        assert_eq!(merged.remote_user.as_deref(), Some("dev"));
+        assert_eq!(merged.on_create_commands.len(), 2);
+        assert_eq!(
+            merged.remote_env.get("KEPT").map(|v| v.as_str()),
+            Some("image")
+        );
+        assert!(!merged.remote_env.contains_key("UNSET"));
+        assert_eq!(
+            merged.mounts,
+            vec![mount("b", "/data"), mount("c", "/cache")]
+        );
+        assert_eq!(
+            merged.forward_ports,
+            vec![
+                ForwardPort::Number(3000),
+                ForwardPort::Address("db:5432".to_string())
+            ]
+        );
+    }
+}
@@ -1,121 +1,564 @@
 pub mod config;
-
-use crate::provider::docker::Docker;
-use crate::provider::docker_compose::DockerCompose;
-use crate::provider::podman::Podman;
-use crate::provider::podman_compose::PodmanCompose;
+pub mod fingerprint;
+pub mod metadata;
+pub mod schema;
+pub mod session;
+pub mod status;
+pub mod variables;
+
+use crate::error::check;
+use crate::error::DevconError;
+use crate::error::Result;
+use crate::provider::compose::Compose;
+use crate::provider::container::Container;
+use crate::provider::detect;
+#[cfg(unix)]
+use crate::provider::docker_api::DockerApi;
+use crate::provider::engine::Engine;
+#[cfg(unix)]
+use crate::provider::http::Client;
+use crate::provider::pod::Pod;
+use crate::provider::Exec;
+use crate::provider::Labels;
+use crate::provider::Logs;
 use crate::provider::Provider;
+use crate::provider::CONFIG_HASH_LABEL;
+use crate::provider::INPUTS_HASH_LABEL;
+use crate::settings::ComposeBackend;
 use crate::settings::Settings;
+use crate::settings::StaleAction;
+use colored::Colorize;
 use config::Config;
+use config::ShutdownAction;
+use config::WaitFor;
+use metadata::MergedMetadata;
+use metadata::Metadata;
+use session::Session;
+use sha2::Digest;
+use sha2::Sha256;
+use std::cell::OnceCell;
+use std::collections::BTreeMap;
+use std::io::IsTerminal;
 use std::path::Path;
 use std::path::PathBuf;
 
 pub struct Devcontainer {
     config: Config,
+    directory: PathBuf,
+    /// The hash of the build inputs, computed on first use.
+    inputs_hash: OnceCell<Option<String>>,
+    /// The devcontainer.json entry as the image label stores it.
+    label_entry: Metadata,
+    labels: Labels,
+    metadata: Vec<Metadata>,
     provider: Box<dyn Provider>,
     settings: Settings,
 }
 
 impl Devcontainer {
-    pub fn load(directory: PathBuf) -> Self {
+    pub fn load(directory: PathBuf) -> Result<Self> {
         let file = directory.join(".devcontainer").join("devcontainer.json");
-        let config = Config::parse(&file).expect("could not find devcontainer.json");
-        let settings = Settings::load();
-        let provider = build_provider(&directory, &settings, &config);
+        let config = Config::parse(&file)?;
+        for issue in schema::validate_file(&file)? {
+            eprintln!("{} {}", "Warning:".yellow().bold(), issue);
+        }
+
+        let mut settings = Settings::load()?;
+
+        // Detected once, so rebuilding the provider does not probe again
+        if settings.provider == crate::settings::Provider::Auto {
+            settings.provider = detect::provider(config.is_compose())?;
+        }
 
-        Self {
+        let labels = Labels {
+            local_folder: directory.to_string_lossy().to_string(),
+            config_file: file.to_string_lossy().to_string(),
+            config_hash: hash(&std::fs::read(&file)?),
+            inputs_hash: None,
+            provider: provider_name(&settings, &config),
+        };
+
+        let provider = build_provider(&directory, &settings, &config, &labels)?;
+
+        Ok(Self {
             config: config.clone(),
+            directory,
+            inputs_hash: OnceCell::new(),
+            label_entry: Config::label_metadata(&file)?,
+            labels,
+            metadata: vec![],
             provider,
             settings,
-        }
+        })
     }
 
-    pub fn run(&self, use_cache: bool) -> std::io::Result<()> {
+    pub fn run(&mut self, use_cache: bool) -> Result<()> {
+        let session = Session::open(&self.config.safe_name(&self.directory))?;
+        self.prepare(use_cache, &session, WaitFor::PostStart)?;
+
+        // Each session gets its own shell. Its exit status is the user's,
+        // not a failure of devcon.
+        self.check_interactive()?;
+        self.provider.exec(&Exec {
+            args: login_shell("-l", None),
+            env: self.merged_metadata().remote_env.into_iter().collect(),
+            interactive: true,
+            tty: std::io::stdin().is_terminal(),
+        })?;
+
+        // Only the last session to close shuts the container down
         let provider = &self.provider;
+        if session.is_last()? {
+            let stopped = match self.config.shutdown_action() {
+                ShutdownAction::None => true,
+                ShutdownAction::StopContainer => provider.stop_container()?,
+                ShutdownAction::StopCompose => provider.stop()?,
+            };
+            check(stopped, DevconError::EngineCommand("stop"))?;
+        }
+
+        Ok(())
+    }
+
+    /// Brings the container up without opening a shell, for CI and editors
+    /// that connect on their own. Once the lifecycle commands up to waitFor
+    /// ran, `report` gets what a client needs to connect, then the remaining
+    /// stages run.
+    pub fn up(&mut self, use_cache: bool, report: impl FnOnce(serde_json::Value)) -> Result<()> {
+        let session = Session::open(&self.config.safe_name(&self.directory))?;
+        let wait_for = self.config.wait_for;
+        self.prepare(use_cache, &session, wait_for)?;
+
+        report(serde_json::json!({
+            "outcome": "success",
+            "containerId": self.provider.id()?,
+            "remoteUser": self.remote_user(),
+            "remoteWorkspaceFolder": &self.config.workspace_folder,
+        }));
+
+        if session.is_last()? {
+            self.run_hooks(|stage| stage > wait_for)?;
+        }
+
+        Ok(())
+    }
 
+    /// Creates and starts the container and, for the first session, runs
+    /// the lifecycle commands up to `wait_for`.
+    fn prepare(&mut self, use_cache: bool, session: &Session, wait_for: WaitFor) -> Result<()> {
+        if self.is_stale()? {
+            self.replace_stale(session)?;
+        }
+
+        self.keep_labels()?;
         self.create(use_cache)?;
-        if !provider.running()? {
-            provider.start()?;
+
+        // Read before starting, so a compose project's override is rendered
+        // the same on every run
+        self.load_metadata()?;
+        if !self.provider.running()? {
+            check(self.provider.start()?, DevconError::EngineCommand("start"))?;
         }
 
-        self.post_create()?;
-        provider.restart()?;
-        provider.attach()?;
+        check(self.provider.sync()?, DevconError::EngineCommand("sync"))?;
 
-        if self.config.should_shutdown() {
-            provider.stop()?;
+        // Other sessions already ran the hooks in the same container
+        if session.is_last()? {
+            self.post_create(wait_for)?;
         }
 
         Ok(())
     }
 
-    pub fn rebuild(&self, use_cache: bool) -> std::io::Result<()> {
+    /// Prints the logs of the container, or of a compose project's services.
+    pub fn logs(&self, logs: &Logs) -> Result<()> {
+        if logs.service.is_some() && !self.config.is_compose() {
+            return Err(DevconError::InvalidConfig(
+                "Only compose projects have services".to_string(),
+            ));
+        }
+
+        check(
+            self.provider.logs(logs)?,
+            DevconError::EngineCommand("read the logs of"),
+        )
+    }
+
+    /// Stops the container, or every service of a compose project.
+    pub fn stop(&self) -> Result<()> {
+        // Not just the running check, a compose project's other services
+        // can be up while the primary one is stopped
+        if !self.provider.exists()? {
+            println!(
+                "{} has no containers",
+                self.config.safe_name(&self.directory)
+            );
+            return Ok(());
+        }
+
+        check(self.provider.stop()?, DevconError::EngineCommand("stop"))
+    }
+
+    /// Removes the project's containers, and optionally their volumes and the
+    /// images devcon built.
+    pub fn down(&self, volumes: bool, images: bool) -> Result<()> {
+        if !self.provider.exists()? {
+            println!(
+                "{} has no containers",
+                self.config.safe_name(&self.directory)
+            );
+            return Ok(());
+        }
+
+        check(
+            self.provider.down(volumes, images)?,
+            DevconError::EngineCommand("remove"),
+        )
+    }
+
+    pub fn rebuild(&mut self, use_cache: bool) -> Result<()> {
         let provider = &self.provider;
         if provider.exists()? {
             provider.stop()?;
-            provider.rm()?;
+            check(provider.rm()?, DevconError::EngineCommand("remove"))?;
         }
 
         self.run(use_cache)
     }
 
-    fn create(&self, use_cache: bool) -> std::io::Result<()> {
-        let provider = &self.provider;
+    /// Runs a command in the running container as the remote user, with the
+    /// user's probed environment and remoteEnv, and returns its exit code.
+    pub fn exec(&mut self, args: Vec<String>) -> Result<i32> {
+        if !self.provider.running()? {
+            return Err(DevconError::NotRunning);
+        }
 
-        if !provider.exists()? {
-            provider.build(use_cache)?;
-            provider.create(self.create_args())?;
+        self.load_metadata()?;
+
+        let mut env = self.probe_env()?;
+        env.extend(self.merged_metadata().remote_env);
+        self.check_interactive()?;
+
+        let code = self.provider.exec(&Exec {
+            args,
+            env,
+            interactive: true,
+            tty: std::io::stdin().is_terminal(),
+        })?;
+
+        Ok(code)
+    }
+
+    /// The API has no raw connection for stdin and terminals, so interactive
+    /// commands fall back to the docker CLI, which has to be installed.
+    fn check_interactive(&self) -> Result<()> {
+        if self.settings.provider != crate::settings::Provider::DockerApi {
+            return Ok(());
         }
 
-        Ok(())
+        match engine(&self.settings, &self.config).missing(false) {
+            Some(program) => Err(DevconError::EngineNotFound(Some(program))),
+            None => Ok(()),
+        }
     }
 
-    fn post_create(&self) -> std::io::Result<()> {
-        let provider = &self.provider;
+    /// Everything devcon will use to run the project: devcontainer.json with
+    /// its variables substituted, the metadata merged from the image and the
+    /// values devcon computes, like the container name.
+    pub fn read_configuration(&mut self) -> Result<serde_json::Value> {
+        self.load_metadata()?;
+
+        let configuration = Config::parse_value(Path::new(&self.labels.config_file))?;
+        let name = self.config.safe_name(&self.directory);
+        let engine = engine(&self.settings, &self.config);
+        let (context, host) = connection(&self.settings, &self.config);
+
+        let workspace_mount = if self.config.is_compose() {
+            None
+        } else if engine.remote {
+            Some(format!(
+                "type=volume,source={}-workspace,target={}",
+                &name, &self.config.workspace_folder
+            ))
+        } else {
+            Some(format!(
+                "type=bind,source={},target={}",
+                self.directory.display(),
+                &self.config.workspace_folder
+            ))
+        };
+
+        let mut merged = serde_json::to_value(self.merged_metadata())?;
+        merged["mounts"] = self
+            .merged_metadata()
+            .mounts
+            .iter()
+            .map(|mount| mount.to_string())
+            .collect();
+
+        Ok(serde_json::json!({
+            "configuration": configuration,
+            "workspace": {
+                "workspaceFolder": &self.config.workspace_folder,
+                "workspaceMount": workspace_mount,
+            },
+            "mergedConfiguration": merged,
+            "containerName": &name,
+            "imageName": (!self.config.is_compose()).then(|| format!("devcon/{}", &name)),
+            "provider": &self.settings.provider,
+            "composeBackend": self.config.is_compose().then_some(&self.settings.compose_backend),
+            "engine": &engine.command,
+            "context": context,
+            "host": host,
+            "remote": engine.remote,
+            "labels": self.labels.all().into_iter().collect::<BTreeMap<_, _>>(),
+        }))
+    }
+
+    /// Whether the container was built from a different devcontainer.json,
+    /// Dockerfile or build context than the one on disk.
+    fn is_stale(&self) -> Result<bool> {
+        if !self.provider.exists()? {
+            return Ok(false);
+        }
+
+        let current = match self.inputs_hash() {
+            Some(current) => current,
+            None => return Ok(false),
+        };
+        let built = self.provider.container_label(INPUTS_HASH_LABEL)?;
 
-        if let Some(command) = self.config.on_create_command.clone() {
-            provider.exec(command)?;
+        Ok(built.as_deref() != Some(current))
+    }
+
+    /// Hashes the build inputs, which reads every file the Dockerfiles copy
+    /// in, so only commands that check or create the container do this.
+    /// Inputs that can't be read only disable the staleness check.
+    fn inputs_hash(&self) -> Option<&str> {
+        self.inputs_hash
+            .get_or_init(|| {
+                let file = Path::new(&self.labels.config_file);
+                let name = self.config.safe_name(&self.directory);
+
+                fingerprint::inputs(&self.directory, file, &self.config, &name)
+                    .map_err(|error| {
+                        eprintln!(
+                            "{} Could not hash the build inputs: {}",
+                            "Warning:".yellow().bold(),
+                            error
+                        );
+                    })
+                    .ok()
+            })
+            .as_deref()
+    }
+
+    /// Removes a stale container when the settings ask for it, so it is
+    /// created again from the current inputs.
+    fn replace_stale(&self, session: &Session) -> Result<()> {
+        let warning = "The container is out of date with devcontainer.json or the Dockerfile";
+
+        // Other sessions are attached to it, so it can't be replaced now
+        let replace = match self.settings.stale {
+            _ if !session.is_last()? => false,
+            StaleAction::Warn => false,
+            StaleAction::Rebuild => true,
+            // Stdout and stdin belong to the client with --json
+            StaleAction::Prompt if crate::provider::json() => false,
+            StaleAction::Prompt => {
+                eprint!("{}, rebuild it? [y/N] ", warning.yellow());
+
+                let mut answer = String::new();
+                std::io::stdin().read_line(&mut answer)?;
+                answer.trim().eq_ignore_ascii_case("y")
+            }
+        };
+
+        if replace {
+            self.provider.stop()?;
+            check(self.provider.rm()?, DevconError::EngineCommand("remove"))?;
+        } else {
+            eprintln!("{}, run devcon rebuild to update it", warning.yellow());
         }
 
-        if let Some(command) = self.config.update_content_command.clone() {
-            provider.exec(command)?;
+        Ok(())
+    }
+
+    /// Labels an existing container's provider with the hashes it was
+    /// created with, which commands that don't create it can't compute.
+    fn keep_labels(&mut self) -> Result<()> {
+        if !self.provider.exists()? {
+            return Ok(());
         }
 
-        if let Some(command) = self.config.post_create_command.clone() {
-            provider.exec(command)?;
+        self.labels.keep_hashes(
+            self.provider.container_label(CONFIG_HASH_LABEL)?,
+            self.provider.container_label(INPUTS_HASH_LABEL)?,
+        );
+        self.provider =
+            build_provider(&self.directory, &self.settings, &self.config, &self.labels)?;
+
+        Ok(())
+    }
+
+    fn create(&mut self, use_cache: bool) -> Result<()> {
+        if !self.provider.exists()? {
+            check(self.provider.build(use_cache)?, DevconError::BuildFailed)?;
+
+            // Reloading the metadata below rebuilds the provider, so the
+            // container is created with this label
+            self.labels.inputs_hash = self.inputs_hash().map(|hash| hash.to_string());
+
+            // A freshly built image only carries the metadata of its base
+            // image, so label it with the merged entries.
+            self.load_metadata()?;
+            let entries = Metadata::entries(&self.metadata, self.label_entry.clone());
+
+            // The container works without it, so a failure only warns
+            let label = serde_json::to_string(&entries)?;
+            let labelled = self.provider.label(label).and_then(|labelled| {
+                labelled
+                    .then_some(())
+                    .ok_or_else(|| std::io::Error::other("the engine command failed"))
+            });
+            if let Err(error) = labelled {
+                eprintln!(
+                    "{} Could not label the image with its metadata: {}",
+                    "Warning:".yellow().bold(),
+                    error
+                );
+            }
+
+            check(
+                self.provider.create(self.create_args())?,
+                DevconError::EngineCommand("create"),
+            )?;
         }
 
+        Ok(())
+    }
+
+    /// Reads the image metadata label and rebuilds the provider so it uses
+    /// the merged remote user, env, mounts and forwarded ports.
+    fn load_metadata(&mut self) -> Result<()> {
+        self.metadata = match self.provider.metadata()? {
+            Some(label) => Metadata::parse_label(&label)?,
+            None => vec![],
+        };
+
+        let merged = self.merged_metadata();
+        let mut config = self.config.clone();
+        config.remote_user = merged.remote_user;
+        config.remote_env = merged
+            .remote_env
+            .into_iter()
+            .map(|(key, value)| (key, Some(value)))
+            .collect();
+        config.container_env = merged.container_env;
+        config.mounts = merged.mounts;
+        config.forward_ports = merged.forward_ports;
+        self.provider = build_provider(&self.directory, &self.settings, &config, &self.labels)?;
+
+        Ok(())
+    }
+
+    /// Reads the environment the remote user's shell sets up, as picked by
+    /// userEnvProbe, so commands see the same PATH as an interactive shell.
+    fn probe_env(&self) -> Result<BTreeMap<String, String>> {
+        let flags = match self.config.user_env_probe.flags() {
+            Some(flags) => flags,
+            None => return Ok(BTreeMap::new()),
+        };
+
+        let args = login_shell(flags, Some("cat /proc/self/environ"));
+        let output = self.provider.exec_output(&Exec {
+            args,
+            ..Default::default()
+        })?;
+
+        Ok(parse_environ(&output))
+    }
+
+    fn merged_metadata(&self) -> MergedMetadata {
+        Metadata::merge(&Metadata::entries(&self.metadata, self.config.metadata()))
+    }
+
+    fn remote_user(&self) -> String {
+        self.merged_metadata()
+            .remote_user
+            .unwrap_or_else(|| self.config.remote_user())
+    }
+
+    /// Runs the lifecycle commands, stopping after the `wait_for` stage.
+    fn post_create(&self, wait_for: WaitFor) -> Result<()> {
+        self.run_hooks(|stage| stage <= wait_for)?;
         self.copy_gitconfig()?;
         self.copy_dotfiles()?;
 
         Ok(())
     }
 
-    fn copy(&self, source: &Path, dest: &str) -> std::io::Result<bool> {
+    /// Runs the lifecycle commands of the stages `include` picks, in order.
+    fn run_hooks(&self, include: impl Fn(WaitFor) -> bool) -> Result<()> {
+        let provider = &self.provider;
+        let merged = self.merged_metadata();
+
+        let stages = [
+            (
+                WaitFor::OnCreate,
+                "onCreateCommand",
+                &merged.on_create_commands,
+            ),
+            (
+                WaitFor::UpdateContent,
+                "updateContentCommand",
+                &merged.update_content_commands,
+            ),
+            (
+                WaitFor::PostCreate,
+                "postCreateCommand",
+                &merged.post_create_commands,
+            ),
+        ];
+
+        for (_, stage, hooks) in stages.into_iter().filter(|(s, _, _)| include(*s)) {
+            for command in hooks.iter().flat_map(|hook| hook.commands()) {
+                let code = provider.exec(&Exec::shell(command.clone()))?;
+                check(code == 0, DevconError::LifecycleHook { stage, command })?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn copy(&self, source: &Path, dest: &str) -> Result<()> {
         if source.exists() {
             let provider = &self.provider;
             let destpath = PathBuf::from(dest);
             let basedir = destpath.parent().and_then(|p| p.to_str()).unwrap();
             let destination = if source.is_dir() { basedir } else { dest };
 
-            provider.exec(format!("mkdir -p {}", basedir))?;
-            provider.cp(
+            provider.exec(&Exec::shell(format!("mkdir -p {}", basedir)))?;
+            let copied = provider.cp(
                 source.to_string_lossy().to_string(),
                 destination.to_string(),
-            )
+            )?;
+            check(copied, DevconError::EngineCommand("copy files into"))
         } else {
             Err(std::io::Error::new(
                 std::io::ErrorKind::NotFound,
                 format!("File not found {:?}", source),
-            ))
+            )
+            .into())
         }
     }
 
-    fn copy_dotfiles(&self) -> std::io::Result<()> {
-        let homedir = if self.config.remote_user == "root" {
+    fn copy_dotfiles(&self) -> Result<()> {
+        let remote_user = self.remote_user();
+        let homedir = if remote_user == "root" {
             PathBuf::from("/root")
         } else {
-            PathBuf::from("/home").join(&self.config.remote_user)
+            PathBuf::from("/home").join(&remote_user)
         };
 
         for file in &self.settings.dotfiles {
@@ -130,22 +573,38 @@ impl Devcontainer {
         Ok(())
     }
 
-    fn copy_gitconfig(&self) -> std::io::Result<bool> {
+    fn copy_gitconfig(&self) -> Result<()> {
         let path = shellexpand::tilde("~/.gitconfig").to_string();
         let file = PathBuf::from(path);
-        let dest = format!("/home/{}/.gitconfig", self.config.remote_user);
+        let dest = format!("/home/{}/.gitconfig", self.remote_user());
 
         self.copy(&file, &dest)
     }
 
     pub fn create_args(&self) -> Vec<String> {
         let mut args = vec![];
+        let merged = self.merged_metadata();
 
-        for (key, value) in &self.config.remote_env {
+        for (key, value) in merged.container_env.iter().chain(&merged.remote_env) {
             args.push("-e".to_string());
             args.push(format!("{}={}", key, value));
         }
 
+        for mount in &merged.mounts {
+            args.push("--mount".to_string());
+            args.push(mount.to_string());
+        }
+
+        for capability in &self.config.cap_add {
+            args.push("--cap-add".to_string());
+            args.push(capability.clone());
+        }
+
+        for option in &self.config.security_opt {
+            args.push("--security-opt".to_string());
+            args.push(option.clone());
+        }
+
         let workspace_folder = self.config.workspace_folder.clone();
         args.push("-w".to_string());
         args.push(workspace_folder);
@@ -158,80 +617,211 @@ impl Devcontainer {
     }
 }
 
-fn build_provider(directory: &Path, settings: &Settings, config: &Config) -> Box<dyn Provider> {
+/// Runs the remote user's shell from the passwd database, falling back to
+/// `sh`, with `flags` and an optional command.
+fn login_shell(flags: &str, command: Option<&str>) -> Vec<String> {
+    let mut script = format!(
+        "shell=$(getent passwd \"$(id -un)\" 2>/dev/null | cut -d: -f7); \
+         exec \"${{shell:-/bin/sh}}\" {}",
+        flags
+    );
+    if let Some(command) = command {
+        script.push_str(&format!(" '{}'", command));
+    }
+
+    vec!["sh".to_string(), "-c".to_string(), script]
+}
+
+/// Parses NUL separated `KEY=value` pairs. Anything a profile printed before
+/// them ends up ahead of the first pair, so it is dropped.
+fn parse_environ(output: &str) -> BTreeMap<String, String> {
+    let mut entries = output.split('\0');
+    let first = entries
+        .next()
+        .map(|entry| entry.rsplit('\n').next().unwrap_or(entry));
+
+    first
+        .into_iter()
+        .chain(entries)
+        .filter_map(|entry| entry.split_once('='))
+        .filter(|(key, _)| !matches!(*key, "PWD" | "OLDPWD" | "SHLVL" | "_" | ""))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+/// The context and host to run on. The project's take precedence over the
+/// settings.
+fn connection(settings: &Settings, config: &Config) -> (Option<String>, Option<String>) {
+    let devcon = &config.customizations.devcon;
+    let context = devcon.context.clone().or_else(|| settings.context.clone());
+    let host = devcon.host.clone().or_else(|| settings.host.clone());
+
+    (context, host)
+}
+
+fn engine(settings: &Settings, config: &Config) -> Engine {
+    let (context, host) = connection(settings, config);
+    base_engine(settings).connect(context, host)
+}
+
+/// The engine for the provider, before connecting it anywhere.
+fn base_engine(settings: &Settings) -> Engine {
     match settings.provider {
-        crate::settings::Provider::Docker => {
-            if config.is_compose() {
-                let composefile = directory
-                    .join(".devcontainer")
-                    .join(config.docker_compose_file.as_ref().unwrap());
-
-                Box::new(DockerCompose {
-                    build_args: config.build_args(),
-                    directory: directory.to_str().map(|d| d.to_string()).unwrap(),
-                    command: "docker".to_string(),
-                    file: composefile.to_str().unwrap().to_string(),
-                    name: config.safe_name(),
-                    forward_ports: config.forward_ports.clone(),
-                    run_args: config.run_args.clone(),
-                    service: config.service.as_ref().unwrap().to_string(),
-                    user: config.remote_user.clone(),
-                    workspace_folder: config.workspace_folder.clone(),
-                })
-            } else {
-                let dockerfile = directory
-                    .join(".devcontainer")
-                    .join(config.dockerfile().unwrap());
-
-                Box::new(Docker {
-                    build_args: config.build_args(),
-                    directory: directory.to_str().map(|d| d.to_string()).unwrap(),
-                    command: "docker".to_string(),
-                    file: dockerfile.to_str().unwrap().to_string(),
-                    forward_ports: config.forward_ports.clone(),
-                    name: config.safe_name(),
-                    run_args: config.run_args.clone(),
-                    user: config.remote_user.clone(),
-                    workspace_folder: config.workspace_folder.clone(),
-                })
-            }
+        crate::settings::Provider::Auto | crate::settings::Provider::Docker => Engine::docker(),
+        crate::settings::Provider::DockerApi => Engine::docker(),
+        crate::settings::Provider::Podman => Engine::podman(),
+        crate::settings::Provider::Nerdctl => Engine::nerdctl(),
+    }
+}
+
+/// How the project is run, like `docker compose`, for the provider label.
+fn provider_name(settings: &Settings, config: &Config) -> String {
+    let command = base_engine(settings).command;
+
+    // Compose projects never go through the API
+    match settings.provider {
+        crate::settings::Provider::Podman
+            if config.is_compose() && settings.compose_backend == ComposeBackend::Pod =>
+        {
+            "podman pod".to_string()
         }
-        crate::settings::Provider::Podman => {
-            if config.is_compose() {
-                let composefile = directory
-                    .join(".devcontainer")
-                    .join(config.docker_compose_file.as_ref().unwrap());
-
-                Box::new(PodmanCompose {
-                    build_args: config.build_args(),
-                    directory: directory.to_str().map(|d| d.to_string()).unwrap(),
-                    command: "podman-compose".to_string(),
-                    file: composefile.to_str().unwrap().to_string(),
-                    forward_ports: config.forward_ports.clone(),
-                    name: config.safe_name(),
-                    podman_command: "podman".to_string(),
-                    run_args: config.run_args.clone(),
-                    service: config.service.as_ref().unwrap().to_string(),
-                    user: config.remote_user.clone(),
-                    workspace_folder: config.workspace_folder.clone(),
-                })
-            } else {
-                let dockerfile = directory
-                    .join(".devcontainer")
-                    .join(config.dockerfile().unwrap());
-
-                Box::new(Podman {
-                    build_args: config.build_args(),
-                    directory: directory.to_str().map(|d| d.to_string()).unwrap(),
-                    command: "podman".to_string(),
-                    file: dockerfile.to_str().unwrap().to_string(),
-                    forward_ports: config.forward_ports.clone(),
-                    name: config.safe_name(),
-                    run_args: config.run_args.clone(),
-                    user: config.remote_user.clone(),
-                    workspace_folder: config.workspace_folder.clone(),
-                })
-            }
+        _ if config.is_compose() => format!("{} compose", command),
+        crate::settings::Provider::DockerApi => "docker-api".to_string(),
+        _ => command,
+    }
+}
+
+fn build_provider(
+    directory: &Path,
+    settings: &Settings,
+    config: &Config,
+    labels: &Labels,
+) -> Result<Box<dyn Provider>> {
+    let engine = engine(settings, config);
+
+    // Pods are a podman feature, other engines always use their compose tool
+    let pods = settings.provider == crate::settings::Provider::Podman
+        && settings.compose_backend == ComposeBackend::Pod;
+
+    // Only compose projects need the engine binary with the API
+    let api = settings.provider == crate::settings::Provider::DockerApi && !config.is_compose();
+    if let Some(program) = engine
+        .missing(config.is_compose() && !pods)
+        .filter(|_| !api)
+    {
+        return Err(DevconError::EngineNotFound(Some(program)));
+    }
+
+    // Compose files bind mount local paths, which a remote engine can't see
+    if config.is_compose() && engine.remote {
+        return Err(DevconError::InvalidConfig(
+            "Compose projects can't run on a remote engine, use a local context or host"
+                .to_string(),
+        ));
+    }
+
+    if config.is_compose() && pods {
+        Ok(Box::new(Pod {
+            build_args: config.build_args(),
+            cap_add: config.cap_add.clone(),
+            container_user: config.container_user.clone(),
+            engine,
+            env: config.env(),
+            files: compose_files(directory, config),
+            // The services share the pod's network, so it publishes every port
+            forward_ports: config.service_ports().into_values().flatten().collect(),
+            labels: labels.clone(),
+            mounts: config.mounts.iter().map(|m| m.to_string()).collect(),
+            name: config.safe_name(directory),
+            override_command: config.override_command.unwrap_or(false),
+            run_services: config.run_services(),
+            security_opt: config.security_opt.clone(),
+            service: service(config)?,
+            user: config.remote_user(),
+            workspace_folder: config.workspace_folder.clone(),
+        }))
+    } else if config.is_compose() {
+        Ok(Box::new(Compose {
+            build_args: config.build_args(),
+            cap_add: config.cap_add.clone(),
+            container_user: config.container_user.clone(),
+            docker_override: OnceCell::new(),
+            engine,
+            env: config.env(),
+            files: compose_files(directory, config),
+            forward_ports: config.service_ports(),
+            labels: labels.clone(),
+            mounts: config.mounts.iter().map(|m| m.to_string()).collect(),
+            name: config.safe_name(directory),
+            override_command: config.override_command.unwrap_or(false),
+            run_services: config.run_services(),
+            security_opt: config.security_opt.clone(),
+            service: service(config)?,
+            user: config.remote_user(),
+            workspace_folder: config.workspace_folder.clone(),
+        }))
+    } else {
+        let dockerfile = directory
+            .join(".devcontainer")
+            .join(config.dockerfile().ok_or_else(|| {
+                DevconError::InvalidConfig("build.dockerfile is required".to_string())
+            })?);
+
+        let container = Container {
+            build_args: config.build_args(),
+            directory: directory.to_str().map(|d| d.to_string()).unwrap(),
+            engine,
+            file: dockerfile.to_str().unwrap().to_string(),
+            forward_ports: config.container_ports(),
+            labels: labels.clone(),
+            name: config.safe_name(directory),
+            run_args: config.run_args.clone(),
+            user: config.remote_user(),
+            workspace_folder: config.workspace_folder.clone(),
+        };
+
+        #[cfg(unix)]
+        if settings.provider == crate::settings::Provider::DockerApi {
+            return Ok(Box::new(DockerApi {
+                cli: container,
+                client: Client::from_host(api_host(settings, config))?,
+            }));
         }
+
+        Ok(Box::new(container))
     }
 }
+
+fn service(config: &Config) -> Result<String> {
+    config.service.clone().ok_or_else(|| {
+        DevconError::InvalidConfig("service is required with dockerComposeFile".to_string())
+    })
+}
+
+/// A hex encoded sha256 of the contents.
+pub(crate) fn hash(contents: &[u8]) -> String {
+    hex(&Sha256::digest(contents))
+}
+
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// The API client talks to a socket, so a context is resolved to its host.
+#[cfg(unix)]
+fn api_host(settings: &Settings, config: &Config) -> Option<String> {
+    let (context, host) = connection(settings, config);
+
+    host.or_else(|| Engine::docker().context_host(&context?))
+}
+
+fn compose_files(directory: &Path, config: &Config) -> Vec<String> {
+    config
+        .compose_files()
+        .iter()
+        .map(|file| {
+            let path = directory.join(".devcontainer").join(file);
+            path.to_str().unwrap().to_string()
+        })
+        .collect()
+}
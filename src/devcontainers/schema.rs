@@ -0,0 +1,257 @@
+use serde_json::Value;
+use std::fmt;
+use std::path::Path;
+
+use super::config;
+use crate::error::Result;
+
+/// A copy of the devcontainer.json schema. Only the keywords it uses are
+/// supported by the validator below.
+const SCHEMA: &str = include_str!("devcontainer.schema.json");
+
+/// Properties that can't be used together, as the spec's image, Dockerfile
+/// and compose variants don't mix.
+const EXCLUSIVE: &[(&str, &str)] = &[
+    ("image", "build"),
+    ("image", "dockerFile"),
+    ("image", "dockerComposeFile"),
+    ("build", "dockerComposeFile"),
+    ("dockerFile", "dockerComposeFile"),
+    ("build", "dockerFile"),
+];
+
+/// A problem found in devcontainer.json.
+#[derive(Debug)]
+pub struct Issue {
+    /// Where the problem is, like `build.args`.
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+/// Checks a devcontainer.json file against the schema. Syntax errors are
+/// left to `Config::parse`.
+pub fn validate_file(file: &Path) -> Result<Vec<Issue>> {
+    let contents = std::fs::read_to_string(file)?;
+
+    match json5::from_str(&contents) {
+        Ok(value) => Ok(validate(&value)),
+        Err(_) => Ok(vec![]),
+    }
+}
+
+pub fn validate(value: &Value) -> Vec<Issue> {
+    let schema: Value = serde_json::from_str(SCHEMA).expect("the embedded schema is valid");
+    let mut issues = vec![];
+
+    Validator {
+        root: &schema,
+        issues: &mut issues,
+    }
+    .check(&schema, value, "");
+
+    for (first, second) in EXCLUSIVE {
+        if value.get(first).is_some() && value.get(second).is_some() {
+            issues.push(Issue {
+                path: String::new(),
+                message: format!("{} and {} can't be used together", first, second),
+            });
+        }
+    }
+
+    if value.get("dockerComposeFile").is_some() && value.get("service").is_none() {
+        issues.push(Issue {
+            path: String::new(),
+            message: "dockerComposeFile needs a service".to_string(),
+        });
+    }
+
+    issues
+}
+
+struct Validator<'a> {
+    root: &'a Value,
+    issues: &'a mut Vec<Issue>,
+}
+
+impl<'a> Validator<'a> {
+    fn check(&mut self, schema: &Value, value: &Value, path: &str) {
+        let schema = self.resolve(schema);
+
+        if let Some(options) = schema["anyOf"].as_array() {
+            if options.iter().any(|option| self.matches(option, value)) {
+                return;
+            }
+
+            // When only one option takes this type of value, its problems
+            // say more than that none matched
+            let typed: Vec<&Value> = options
+                .iter()
+                .filter(|option| has_type(&self.resolve(option)["type"], value))
+                .collect();
+            match typed[..] {
+                [option] => self.check(option, value, path),
+                _ => self.issue(path, format!("{} is not allowed here", describe(value))),
+            }
+            return;
+        }
+
+        if !schema["type"].is_null() && !has_type(&schema["type"], value) {
+            let expected = match &schema["type"] {
+                Value::Array(types) => types
+                    .iter()
+                    .filter_map(|t| t.as_str())
+                    .collect::<Vec<_>>()
+                    .join(" or "),
+                t => t.as_str().unwrap_or_default().to_string(),
+            };
+            self.issue(
+                path,
+                format!("expected {}, found {}", expected, describe(value)),
+            );
+            return;
+        }
+
+        if let Some(allowed) = schema["enum"].as_array() {
+            if !allowed.contains(value) {
+                let allowed: Vec<String> = allowed.iter().map(|a| a.to_string()).collect();
+                self.issue(
+                    path,
+                    format!("{} is not one of {}", value, allowed.join(", ")),
+                );
+            }
+        }
+
+        if let Some(number) = value.as_f64() {
+            if schema["minimum"].as_f64().is_some_and(|min| number < min) {
+                self.issue(
+                    path,
+                    format!("{} is less than {}", number, schema["minimum"]),
+                );
+            }
+            if schema["maximum"].as_f64().is_some_and(|max| number > max) {
+                self.issue(
+                    path,
+                    format!("{} is more than {}", number, schema["maximum"]),
+                );
+            }
+        }
+
+        if let Some(items) = value.as_array() {
+            if !schema["items"].is_null() {
+                for (index, item) in items.iter().enumerate() {
+                    self.check(&schema["items"], item, &format!("{}[{}]", path, index));
+                }
+            }
+        }
+
+        if let Some(object) = value.as_object() {
+            for required in schema["required"].as_array().into_iter().flatten() {
+                let required = required.as_str().unwrap_or_default();
+                if !object.contains_key(required) {
+                    self.issue(path, format!("missing property {}", required));
+                }
+            }
+
+            for (key, item) in object {
+                let child = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+
+                if let Some(property) = schema["properties"].get(key) {
+                    self.check(property, item, &child);
+                    continue;
+                }
+
+                match &schema["additionalProperties"] {
+                    Value::Bool(false) => {
+                        let known: Vec<&str> = schema["properties"]
+                            .as_object()
+                            .map(|p| p.keys().map(|k| k.as_str()).collect())
+                            .unwrap_or_default();
+                        let message = match config::closest(key, &known) {
+                            Some(suggestion) => {
+                                format!("unknown property, did you mean {}?", suggestion)
+                            }
+                            None => "unknown property".to_string(),
+                        };
+                        self.issue(&child, message);
+                    }
+                    additional @ Value::Object(_) => self.check(additional, item, &child),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    /// Whether a value passes a schema, without reporting anything.
+    fn matches(&self, schema: &Value, value: &Value) -> bool {
+        let mut issues = vec![];
+        Validator {
+            root: self.root,
+            issues: &mut issues,
+        }
+        .check(schema, value, "");
+
+        issues.is_empty()
+    }
+
+    fn resolve<'s>(&self, schema: &'s Value) -> &'s Value
+    where
+        'a: 's,
+    {
+        match schema["$ref"].as_str() {
+            Some(reference) => {
+                let pointer = reference.trim_start_matches('#');
+                self.root.pointer(pointer).unwrap_or(&Value::Null)
+            }
+            None => schema,
+        }
+    }
+
+    fn issue(&mut self, path: &str, message: String) {
+        self.issues.push(Issue {
+            path: path.to_string(),
+            message,
+        });
+    }
+}
+
+fn has_type(expected: &Value, value: &Value) -> bool {
+    match expected {
+        Value::Array(types) => types.iter().any(|t| has_type(t, value)),
+        Value::String(t) => match t.as_str() {
+            "object" => value.is_object(),
+            "array" => value.is_array(),
+            "string" => value.is_string(),
+            "boolean" => value.is_boolean(),
+            "integer" => value.is_i64() || value.is_u64(),
+            "number" => value.is_number(),
+            "null" => value.is_null(),
+            _ => true,
+        },
+        _ => true,
+    }
+}
+
+fn describe(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
@@ -0,0 +1,84 @@
+use std::path::PathBuf;
+
+use crate::settings;
+
+/// Marks a running devcon process attached to a container. Sessions are pid
+/// files under the cache directory, removed again when dropped.
+pub struct Session {
+    dir: PathBuf,
+    file: PathBuf,
+}
+
+impl Session {
+    pub fn open(name: &str) -> std::io::Result<Self> {
+        let dir = settings::cache_dir().join("sessions").join(name);
+        std::fs::create_dir_all(&dir)?;
+
+        let file = dir.join(std::process::id().to_string());
+        std::fs::write(&file, "")?;
+
+        Ok(Self { dir, file })
+    }
+
+    /// Counts the other live sessions for the same container, cleaning up
+    /// files left behind by processes that no longer exist.
+    pub fn others(&self) -> std::io::Result<usize> {
+        let mut count = 0;
+
+        for entry in std::fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path == self.file {
+                continue;
+            }
+
+            let alive = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .map(is_alive)
+                .unwrap_or(false);
+
+            if alive {
+                count += 1;
+            } else {
+                std::fs::remove_file(&path)?;
+            }
+        }
+
+        Ok(count)
+    }
+
+    pub fn is_last(&self) -> std::io::Result<bool> {
+        Ok(self.others()? == 0)
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.file);
+    }
+}
+
+fn is_alive(pid: &str) -> bool {
+    if pid.is_empty() || !pid.bytes().all(|byte| byte.is_ascii_digit()) {
+        return false;
+    }
+
+    alive(pid)
+}
+
+#[cfg(target_os = "linux")]
+fn alive(pid: &str) -> bool {
+    std::path::Path::new("/proc").join(pid).exists()
+}
+
+// Without procfs, ask kill whether a signal could be delivered
+#[cfg(not(target_os = "linux"))]
+fn alive(pid: &str) -> bool {
+    std::process::Command::new("kill")
+        .arg("-0")
+        .arg(pid)
+        .stderr(std::process::Stdio::null())
+        .status()
+        .map(|status| status.success())
+        .unwrap_or(false)
+}
@@ -0,0 +1,148 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use super::base_engine;
+use super::config::Config;
+use super::fingerprint;
+use crate::error::DevconError;
+use crate::error::Result;
+use crate::provider::detect;
+use crate::provider::CONFIG_FILE_LABEL;
+use crate::provider::INPUTS_HASH_LABEL;
+use crate::provider::LOCAL_FOLDER_LABEL;
+use crate::provider::PROVIDER_LABEL;
+use crate::settings::Provider;
+use crate::settings::Settings;
+use crate::time;
+
+/// A container devcon created, as listed by `devcon status`.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Status {
+    pub project: String,
+    /// The name from devcontainer.json, when it can still be read.
+    pub name: Option<String>,
+    pub container: String,
+    pub id: String,
+    pub provider: Option<String>,
+    pub state: String,
+    /// Seconds since the container started, while it runs.
+    pub uptime: Option<u64>,
+    /// Published ports, as `host->container/protocol`.
+    pub ports: Vec<String>,
+    /// Whether devcontainer.json, the Dockerfile or the files it copies in
+    /// changed since the container was created. Unknown when the config
+    /// can't be read.
+    pub drifted: Option<bool>,
+}
+
+/// Finds every container devcon created on the configured engine, for any
+/// project, ordered by project.
+pub fn list() -> Result<Vec<Status>> {
+    let mut settings = Settings::load()?;
+    if settings.provider == Provider::Auto {
+        settings.provider = detect::provider(false)?;
+    }
+
+    #[cfg(unix)]
+    let containers = if settings.provider == Provider::DockerApi {
+        let client = crate::provider::http::Client::from_host(settings.host.clone())?;
+        crate::provider::docker_api::inspect_all(&client)?
+    } else {
+        inspect_cli(&settings)?
+    };
+    #[cfg(not(unix))]
+    let containers = inspect_cli(&settings)?;
+
+    let mut statuses: Vec<Status> = containers.iter().map(status).collect();
+    statuses.sort_by(|a, b| a.project.cmp(&b.project));
+
+    Ok(statuses)
+}
+
+fn inspect_cli(settings: &Settings) -> Result<Vec<Value>> {
+    let engine = base_engine(settings).connect(settings.context.clone(), settings.host.clone());
+    if let Some(program) = engine.missing(false) {
+        return Err(DevconError::EngineNotFound(Some(program)));
+    }
+
+    Ok(crate::provider::inspect_all(&engine)?)
+}
+
+fn status(container: &Value) -> Status {
+    let labels = &container["Config"]["Labels"];
+    let label = |name: &str| labels[name].as_str().map(|value| value.to_string());
+
+    let project = label(LOCAL_FOLDER_LABEL).unwrap_or_default();
+    let config = label(CONFIG_FILE_LABEL).and_then(|file| {
+        let config = Config::parse(Path::new(&file)).ok()?;
+        Some((file, config))
+    });
+
+    let drifted = config.as_ref().and_then(|(file, config)| {
+        let directory = Path::new(&project);
+        let name = config.safe_name(directory);
+        let inputs = fingerprint::inputs(directory, Path::new(file), config, &name).ok()?;
+
+        Some(label(INPUTS_HASH_LABEL) != Some(inputs))
+    });
+
+    let state = &container["State"];
+    let uptime = match state["Running"].as_bool() {
+        Some(true) => state["StartedAt"]
+            .as_str()
+            .and_then(time::unix_time)
+            .map(|started| time::now().saturating_sub(started)),
+        _ => None,
+    };
+
+    Status {
+        name: config.map(|(_, config)| config.name().to_string()),
+        container: container["Name"]
+            .as_str()
+            .unwrap_or_default()
+            .trim_start_matches('/')
+            .to_string(),
+        id: container["Id"]
+            .as_str()
+            .unwrap_or_default()
+            .chars()
+            .take(12)
+            .collect(),
+        provider: label(PROVIDER_LABEL),
+        state: state["Status"].as_str().unwrap_or("unknown").to_string(),
+        uptime,
+        ports: ports(&container["NetworkSettings"]["Ports"]),
+        drifted,
+        project,
+    }
+}
+
+/// Lists published ports once, even when bound on both IPv4 and IPv6.
+fn ports(bindings: &Value) -> Vec<String> {
+    let mut ports = BTreeSet::new();
+
+    for (port, hosts) in bindings.as_object().into_iter().flatten() {
+        for host in hosts.as_array().into_iter().flatten() {
+            if let Some(host_port) = host["HostPort"].as_str() {
+                ports.insert(format!("{}->{}", host_port, port));
+            }
+        }
+    }
+
+    ports.into_iter().collect()
+}
+
+/// A short duration like `3h 12m`.
+pub fn duration(seconds: u64) -> String {
+    let (days, hours, minutes) = (seconds / 86400, seconds / 3600 % 24, seconds / 60 % 60);
+
+    match (days, hours, minutes) {
+        (0, 0, 0) => format!("{}s", seconds),
+        (0, 0, minutes) => format!("{}m", minutes),
+        (0, hours, minutes) => format!("{}h {}m", hours, minutes),
+        (days, hours, _) => format!("{}d {}h", days, hours),
+    }
+}
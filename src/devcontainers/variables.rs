@@ -0,0 +1,135 @@
+use serde_json::Value;
+use std::path::Path;
+
+/// The values `${...}` variables in devcontainer.json are replaced with.
+pub struct Variables {
+    pub local_workspace_folder: String,
+    pub container_workspace_folder: String,
+    /// Whether `${localEnv:...}` and `${env:...}` are replaced.
+    pub env: bool,
+}
+
+impl Variables {
+    pub fn new(directory: &Path, workspace_folder: &str) -> Self {
+        Self {
+            local_workspace_folder: directory.to_string_lossy().to_string(),
+            container_workspace_folder: workspace_folder.to_string(),
+            env: true,
+        }
+    }
+
+    /// Replaces variables in every string of the config, keys included.
+    pub fn substitute(&self, value: &mut Value) {
+        match value {
+            Value::String(string) => *string = self.replace(string),
+            Value::Array(values) => values.iter_mut().for_each(|v| self.substitute(v)),
+            Value::Object(object) => {
+                let entries = std::mem::take(object);
+                for (key, mut value) in entries {
+                    self.substitute(&mut value);
+                    object.insert(self.replace(&key), value);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Replaces the variables in a string. Unknown variables are left as
+    /// they are.
+    pub fn replace(&self, string: &str) -> String {
+        let mut result = String::new();
+        let mut rest = string;
+
+        while let Some(start) = rest.find("${") {
+            result.push_str(&rest[..start]);
+
+            let variable = &rest[start + 2..];
+            let end = match variable.find('}') {
+                Some(end) => end,
+                None => {
+                    rest = &rest[start..];
+                    break;
+                }
+            };
+
+            match self.lookup(&variable[..end]) {
+                Some(value) => result.push_str(&value),
+                None => result.push_str(&rest[start..start + end + 3]),
+            }
+            rest = &variable[end + 1..];
+        }
+
+        result.push_str(rest);
+        result
+    }
+
+    fn lookup(&self, variable: &str) -> Option<String> {
+        let mut parts = variable.splitn(3, ':');
+
+        match parts.next()? {
+            "localWorkspaceFolder" => Some(self.local_workspace_folder.clone()),
+            "localWorkspaceFolderBasename" => Some(basename(&self.local_workspace_folder)),
+            "containerWorkspaceFolder" => Some(self.container_workspace_folder.clone()),
+            "containerWorkspaceFolderBasename" => Some(basename(&self.container_workspace_folder)),
+            "localEnv" | "env" if self.env => {
+                let name = parts.next()?;
+                let default = parts.next().unwrap_or_default();
+
+                Some(std::env::var(name).unwrap_or_else(|_| default.to_string()))
+            }
+            _ => None,
+        }
+    }
+}
+
+fn basename(path: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Variables;
+    use std::path::Path;
+
+    fn variables() -> Variables {
+        Variables::new(Path::new("/home/me/project"), "/workspaces/project")
+    }
+
+    #[test]
+    fn leaves_unknown_variables() {
+        assert_eq!(
+            variables().replace("${devcontainerId}/${localWorkspaceFolderBasename}"),
+            "${devcontainerId}/project"
+        );
+    }
+
+    #[test]
+    fn leaves_unterminated_variables() {
+        assert_eq!(
+            variables().replace("${containerWorkspaceFolder}/${localEnv:HOME"),
+            "/workspaces/project/${localEnv:HOME"
+        );
+    }
+
+    #[test]
+    fn uses_the_default_of_unset_variables() {
+        let name = "DEVCON_TEST_UNSET_VARIABLE";
+        std::env::remove_var(name);
+
+        assert_eq!(
+            variables().replace(&format!("${{localEnv:{}:fallback:value}}", name)),
+            "fallback:value"
+        );
+        assert_eq!(variables().replace(&format!("${{env:{}}}", name)), "");
+
+        let mut variables = variables();
+        variables.env = false;
+        assert_eq!(
+            variables.replace(&format!("${{localEnv:{}:fallback}}", name)),
+            format!("${{localEnv:{}:fallback}}", name)
+        );
+    }
+}
@@ -0,0 +1,149 @@
+use std::fmt;
+use std::path::PathBuf;
+
+pub type Result<T> = std::result::Result<T, DevconError>;
+
+/// Everything that stops devcon, each with its own exit code.
+#[derive(Debug)]
+pub enum DevconError {
+    ConfigNotFound(PathBuf),
+    ConfigParse(Box<ParseError>),
+    SettingsParse {
+        file: PathBuf,
+        message: String,
+    },
+    /// The config parsed, but devcon can't run it.
+    InvalidConfig(String),
+    /// No engine was detected, or the binary the settings pick is missing.
+    EngineNotFound(Option<String>),
+    BuildFailed,
+    /// An engine command other than the build failed, like `create`.
+    EngineCommand(&'static str),
+    LifecycleHook {
+        stage: &'static str,
+        command: String,
+    },
+    /// A command needs the container running first.
+    NotRunning,
+    Io(std::io::Error),
+}
+
+/// Where and why devcontainer.json couldn't be read.
+#[derive(Debug)]
+pub struct ParseError {
+    pub file: PathBuf,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub message: String,
+    /// The offending line, marked at the column.
+    pub snippet: Option<String>,
+    pub hints: Vec<String>,
+}
+
+impl DevconError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            DevconError::Io(_) => 1,
+            DevconError::ConfigNotFound(_) => 2,
+            DevconError::ConfigParse(_)
+            | DevconError::SettingsParse { .. }
+            | DevconError::InvalidConfig(_) => 3,
+            DevconError::EngineNotFound(_) => 4,
+            DevconError::BuildFailed => 5,
+            DevconError::EngineCommand(_) => 6,
+            DevconError::LifecycleHook { .. } => 7,
+            DevconError::NotRunning => 8,
+        }
+    }
+}
+
+impl fmt::Display for DevconError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DevconError::ConfigNotFound(file) => {
+                write!(f, "Could not find {}", file.display())
+            }
+            DevconError::ConfigParse(error) => write!(f, "{}", error),
+            DevconError::SettingsParse { file, message } => {
+                write!(f, "{}: {}", file.display(), message)
+            }
+            DevconError::InvalidConfig(message) => write!(f, "{}", message),
+            DevconError::EngineNotFound(None) => {
+                write!(
+                    f,
+                    "No container engine found, install docker, podman or nerdctl"
+                )
+            }
+            DevconError::EngineNotFound(Some(program)) => {
+                write!(
+                    f,
+                    "Could not find {} on the PATH, is it installed?",
+                    program
+                )
+            }
+            DevconError::BuildFailed => write!(f, "Building the image failed"),
+            DevconError::EngineCommand(action) => {
+                write!(f, "The container engine could not {} the container", action)
+            }
+            DevconError::LifecycleHook { stage, command } => {
+                write!(f, "The {} command failed: {}", stage, command)
+            }
+            DevconError::NotRunning => {
+                write!(
+                    f,
+                    "The container is not running, start it with devcon start"
+                )
+            }
+            DevconError::Io(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => write!(
+                f,
+                "{}:{}:{}: {}",
+                self.file.display(),
+                line,
+                column,
+                self.message
+            )?,
+            _ => write!(f, "{}: {}", self.file.display(), self.message)?,
+        }
+
+        if let Some(snippet) = &self.snippet {
+            write!(f, "\n{}", snippet)?;
+        }
+
+        for hint in &self.hints {
+            write!(f, "\nhint: {}", hint)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for DevconError {}
+
+impl From<std::io::Error> for DevconError {
+    fn from(error: std::io::Error) -> Self {
+        DevconError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for DevconError {
+    fn from(error: serde_json::Error) -> Self {
+        DevconError::Io(error.into())
+    }
+}
+
+/// Turns a provider's `false` into an error.
+pub fn check(success: bool, error: DevconError) -> Result<()> {
+    if success {
+        Ok(())
+    } else {
+        Err(error)
+    }
+}
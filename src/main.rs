@@ -1,10 +1,13 @@
 use clap::Parser;
 use clap::Subcommand;
+use colored::Colorize;
 
 pub(crate) mod commands;
 pub(crate) mod devcontainers;
+pub(crate) mod error;
 pub(crate) mod provider;
 pub(crate) mod settings;
+pub(crate) mod time;
 
 #[derive(Parser)]
 #[clap(author, version, about, long_about = None)]
@@ -12,32 +15,108 @@ pub(crate) mod settings;
 struct Cli {
     #[clap(subcommand)]
     command: Option<Commands>,
+    /// Explain what devcon is doing
+    #[clap(short, long, global = true)]
+    verbose: bool,
 }
 
 #[derive(Subcommand)]
 enum Commands {
+    /// Remove the project's containers
+    Down {
+        dir: Option<String>,
+        /// Also remove the containers' volumes
+        #[clap(long)]
+        volumes: bool,
+        /// Also remove the images devcon built
+        #[clap(long)]
+        images: bool,
+    },
+    /// Run a command in the running container
+    Exec {
+        #[clap(long)]
+        dir: Option<String>,
+        #[clap(last = true, required = true)]
+        command: Vec<String>,
+    },
+    /// Print the logs of the container, or of a compose project's services
+    Logs {
+        /// Only this compose service
+        service: Option<String>,
+        #[clap(long)]
+        dir: Option<String>,
+        /// Keep printing new output
+        #[clap(short, long)]
+        follow: bool,
+        /// Only output since a timestamp or a duration like 10m
+        #[clap(long)]
+        since: Option<String>,
+    },
+    /// Print the resolved configuration as JSON
+    ReadConfiguration { dir: Option<String> },
     Rebuild {
         dir: Option<String>,
         #[clap(short, long)]
         no_cache: bool,
     },
-    Start {
+    /// Start the container and open a shell in it
+    #[clap(visible_alias = "shell")]
+    Start { dir: Option<String> },
+    /// List devcon's containers across every project
+    #[clap(visible_alias = "ps")]
+    Status {
+        /// Print the containers as JSON
+        #[clap(long)]
+        json: bool,
+    },
+    /// Stop the project's containers
+    Stop { dir: Option<String> },
+    /// Start the container and run its lifecycle commands without a shell
+    Up {
         dir: Option<String>,
+        #[clap(long)]
+        no_cache: bool,
+        /// Print the container id, remote user and workspace folder as JSON
+        #[clap(long)]
+        json: bool,
     },
+    /// Check devcontainer.json against the devcontainer schema
+    Validate { dir: Option<String> },
 }
 
 fn main() {
     let cli = Cli::parse();
+    provider::set_verbose(cli.verbose);
+
+    let result = match &cli.command {
+        Some(Commands::Down {
+            dir,
+            volumes,
+            images,
+        }) => commands::down::run(dir, *volumes, *images),
+        Some(Commands::Exec { dir, command }) => commands::exec::run(dir, command),
+        Some(Commands::Start { dir }) => commands::start::run(dir),
+        Some(Commands::Logs {
+            service,
+            dir,
+            follow,
+            since,
+        }) => commands::logs::run(dir, service, *follow, since),
+        Some(Commands::ReadConfiguration { dir }) => commands::read_configuration::run(dir),
+        Some(Commands::Rebuild { dir, no_cache }) => commands::rebuild::run(dir, !no_cache),
+        Some(Commands::Status { json }) => commands::status::run(*json),
+        Some(Commands::Stop { dir }) => commands::stop::run(dir),
+        Some(Commands::Up {
+            dir,
+            no_cache,
+            json,
+        }) => commands::up::run(dir, !no_cache, *json),
+        Some(Commands::Validate { dir }) => commands::validate::run(dir),
+        None => commands::start::run(&None),
+    };
 
-    match &cli.command {
-        Some(Commands::Start { dir }) => {
-            commands::start::run(dir).unwrap();
-        }
-        Some(Commands::Rebuild { dir, no_cache }) => {
-            commands::rebuild::run(dir, !no_cache).unwrap();
-        }
-        None => {
-            commands::start::run(&None).unwrap();
-        }
+    if let Err(error) = result {
+        eprintln!("{} {}", "Error:".red().bold(), error);
+        std::process::exit(error.exit_code());
     }
 }
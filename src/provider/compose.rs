@@ -0,0 +1,559 @@
+use std::cell::OnceCell;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::env;
+use std::io::Result;
+use std::process::Command;
+use std::process::Stdio;
+
+use super::compose_file;
+use super::compose_file::Service;
+use super::compose_override::BuildOverride;
+use super::compose_override::ComposeOverride;
+use super::compose_override::ServiceOverride;
+use super::compose_override::Volume;
+use super::engine::Engine;
+use super::exec_command;
+use super::exit_code;
+use super::filter_labels;
+use super::inspect_label;
+use super::print_command;
+use super::Exec;
+use super::Labels;
+use super::Logs;
+use super::Provider;
+use super::KEEP_ALIVE;
+use super::METADATA_LABEL;
+use crate::settings;
+
+/// A docker-compose project, run by the engine's compose tool with devcon's
+/// override layered on top.
+#[derive(Debug)]
+pub struct Compose {
+    pub build_args: HashMap<String, String>,
+    pub cap_add: Vec<String>,
+    pub container_user: Option<String>,
+    pub docker_override: OnceCell<String>,
+    pub engine: Engine,
+    pub env: HashMap<String, String>,
+    pub files: Vec<String>,
+    /// The forwarded ports by the service they are on.
+    pub forward_ports: BTreeMap<String, Vec<u16>>,
+    pub labels: Labels,
+    pub mounts: Vec<String>,
+    pub name: String,
+    pub override_command: bool,
+    pub run_services: Vec<String>,
+    pub security_opt: Vec<String>,
+    pub service: String,
+    pub user: String,
+    pub workspace_folder: String,
+}
+
+impl Compose {
+    /// Renders the override into a file scoped to the project, once per
+    /// invocation. The same path is reused, and rewritten, on every run.
+    fn create_docker_compose(&self) -> Result<String> {
+        if let Some(file) = self.docker_override.get() {
+            return Ok(file.clone());
+        }
+
+        let dir = settings::cache_dir().join("compose").join(&self.name);
+        std::fs::create_dir_all(&dir)?;
+        let file = dir.join("docker-compose.override.yml");
+        self.render().write(&file)?;
+
+        let file = file.to_str().expect("could not make override file");
+        Ok(self
+            .docker_override
+            .get_or_init(|| file.to_string())
+            .clone())
+    }
+
+    /// The override for the primary service, and the ports of the others.
+    fn render(&self) -> ComposeOverride {
+        let mut service = ServiceOverride {
+            image: self.built_image(),
+            environment: self.env.clone().into_iter().collect(),
+            ports: self.published_ports(&self.service),
+            labels: self
+                .labels
+                .all()
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+            cap_add: self.cap_add.clone(),
+            security_opt: self.security_opt.clone(),
+            user: self.container_user.clone(),
+            ..Default::default()
+        };
+
+        // Forwards the ssh-agent to the container
+        if let Ok(ssh_auth_sock) = env::var("SSH_AUTH_SOCK") {
+            service
+                .volumes
+                .push(Volume::bind(&ssh_auth_sock, "/ssh-agent"));
+            service
+                .environment
+                .insert("SSH_AUTH_SOCK".to_string(), "/ssh-agent".to_string());
+        };
+
+        service
+            .volumes
+            .extend(self.mounts.iter().filter_map(|mount| Volume::parse(mount)));
+
+        // Keeps the service alive regardless of its own command
+        if self.override_command {
+            service.command = Some(vec![
+                "/bin/sh".to_string(),
+                "-c".to_string(),
+                KEEP_ALIVE.to_string(),
+            ]);
+        }
+
+        let mut docker_override = ComposeOverride::default();
+        for name in self
+            .forward_ports
+            .keys()
+            .filter(|name| **name != self.service)
+        {
+            let ports = ServiceOverride {
+                ports: self.published_ports(name),
+                ..Default::default()
+            };
+            docker_override.services.insert(name.clone(), ports);
+        }
+        docker_override
+            .services
+            .insert(self.service.clone(), service);
+
+        docker_override
+    }
+
+    fn primary(&self) -> Result<Option<Service>> {
+        let services = compose_file::load(&self.files, &self.name)?;
+
+        Ok(services.into_iter().find(|s| s.name == self.service))
+    }
+
+    /// The primary service's image. One devcon builds is given a name, as
+    /// compose tools don't agree on how to name it.
+    fn image(&self, service: &Service) -> Option<String> {
+        match (&service.image, &service.build) {
+            (Some(image), _) => Some(image.clone()),
+            (None, Some(_)) => Some(format!("devcon/{}-{}", &self.name, &service.name)),
+            (None, None) => None,
+        }
+    }
+
+    /// The name given to the primary service's image when it is built and
+    /// doesn't have one.
+    fn built_image(&self) -> Option<String> {
+        let service = self.primary().ok().flatten()?;
+
+        service
+            .image
+            .is_none()
+            .then(|| self.image(&service))
+            .flatten()
+    }
+
+    fn image_exists(&self, image: &str) -> Result<bool> {
+        let mut command = self.engine.command();
+        command
+            .arg("image")
+            .arg("inspect")
+            .arg(image)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+
+        Ok(command.status()?.success())
+    }
+
+    fn published_ports(&self, service: &str) -> Vec<String> {
+        self.forward_ports
+            .get(service)
+            .into_iter()
+            .flatten()
+            .map(|port| format!("{}:{}", port, port))
+            .collect()
+    }
+
+    /// Lists the project's containers by the labels compose tools set,
+    /// optionally limited to the primary service, which carries devcon's
+    /// labels too.
+    fn container_ids(&self, all: bool, service_only: bool) -> Result<Vec<String>> {
+        let mut command = self.engine.command();
+        command.arg("ps").arg(if all { "-aq" } else { "-q" });
+        command
+            .arg("--filter")
+            .arg(format!("label=com.docker.compose.project={}", &self.name));
+
+        if service_only {
+            command.arg("--filter").arg(format!(
+                "label=com.docker.compose.service={}",
+                &self.service
+            ));
+            filter_labels(&mut command, &self.labels);
+        }
+
+        let output = command.output()?.stdout;
+        let value = String::from_utf8(output).unwrap();
+
+        Ok(value.lines().map(|id| id.trim().to_string()).collect())
+    }
+
+    /// The container backing the primary service. Not every compose tool
+    /// supports cp or exec by service name, so the engine is used directly.
+    fn container_id(&self) -> Result<String> {
+        self.container_ids(true, true)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("No container for service {}", &self.service),
+                )
+            })
+    }
+
+    fn compose_command(&self, docker_override: Option<&str>) -> Command {
+        let mut command = self.engine.compose_command();
+
+        for file in &self.files {
+            command.arg("-f").arg(file);
+        }
+
+        if let Some(docker_override) = docker_override {
+            command.arg("-f").arg(docker_override);
+        }
+
+        command.arg("-p").arg(&self.name);
+
+        command
+    }
+}
+
+impl Provider for Compose {
+    fn build(&self, use_cache: bool) -> Result<bool> {
+        let docker_override = self.create_docker_compose()?;
+
+        let mut command = self.compose_command(Some(&docker_override));
+        command.arg("build");
+
+        if !use_cache {
+            command.arg("--no-cache");
+        }
+
+        for (key, value) in &self.build_args {
+            command.arg("--build-arg").arg(format!("{}={}", key, value));
+        }
+
+        print_command(&mut command);
+
+        if !command.status()?.success() {
+            return Ok(false);
+        }
+
+        // Images that aren't built are only pulled by `up`, after their
+        // metadata is needed
+        let image = match self.primary()? {
+            Some(service) if service.build.is_none() => self.image(&service),
+            _ => None,
+        };
+        match image {
+            Some(image) if !self.image_exists(&image)? => {
+                let mut command = self.compose_command(Some(&docker_override));
+                command.arg("pull").arg(&self.service);
+
+                print_command(&mut command);
+
+                Ok(command.status()?.success())
+            }
+            _ => Ok(true),
+        }
+    }
+
+    fn create(&self, _args: Vec<String>) -> Result<bool> {
+        Ok(true)
+    }
+
+    fn start(&self) -> Result<bool> {
+        let docker_override = self.create_docker_compose()?;
+
+        let mut command = self.compose_command(Some(&docker_override));
+        command.arg("up").arg("--detach");
+
+        // Only the listed services are started when runServices is set.
+        for service in &self.run_services {
+            command.arg(service);
+        }
+
+        print_command(&mut command);
+
+        Ok(command.status()?.success())
+    }
+
+    fn stop(&self) -> Result<bool> {
+        let docker_override = self.create_docker_compose()?;
+
+        let mut command = self.compose_command(Some(&docker_override));
+        command.arg("stop");
+
+        print_command(&mut command);
+
+        Ok(command.status()?.success())
+    }
+
+    fn stop_container(&self) -> Result<bool> {
+        let docker_override = self.create_docker_compose()?;
+        let mut command = self.compose_command(Some(&docker_override));
+        command.arg("stop").arg(&self.service);
+
+        print_command(&mut command);
+
+        Ok(command.status()?.success())
+    }
+
+    fn rm(&self) -> Result<bool> {
+        let docker_override = self.create_docker_compose()?;
+
+        let mut command = self.compose_command(Some(&docker_override));
+        command
+            .arg("down")
+            .arg("--remove-orphans")
+            .arg("--rmi")
+            .arg("all");
+
+        print_command(&mut command);
+
+        Ok(command.status()?.success())
+    }
+
+    fn down(&self, volumes: bool, images: bool) -> Result<bool> {
+        let docker_override = self.create_docker_compose()?;
+
+        let mut command = self.compose_command(Some(&docker_override));
+        command.arg("down").arg("--remove-orphans");
+        if volumes {
+            command.arg("--volumes");
+        }
+        // Only the images compose built, not the ones it pulled
+        if images {
+            command.arg("--rmi").arg("local");
+        }
+
+        print_command(&mut command);
+
+        if !command.status()?.success() {
+            return Ok(false);
+        }
+
+        // Named images are kept by `--rmi local`, including the one devcon
+        // names
+        match self.built_image().filter(|_| images) {
+            Some(image) => {
+                let mut command = self.engine.command();
+                command.arg("image").arg("rm").arg(image);
+
+                print_command(&mut command);
+
+                Ok(command.status()?.success())
+            }
+            None => Ok(true),
+        }
+    }
+
+    fn exists(&self) -> Result<bool> {
+        Ok(!self.container_ids(true, false)?.is_empty())
+    }
+
+    /// Other services can keep running after the primary one was stopped,
+    /// as with `shutdownAction: "stopContainer"`, so only it counts.
+    fn running(&self) -> Result<bool> {
+        Ok(!self.container_ids(false, true)?.is_empty())
+    }
+
+    fn id(&self) -> Result<Option<String>> {
+        Ok(self.container_ids(true, true)?.into_iter().next())
+    }
+
+    fn cp(&self, source: String, destination: String) -> Result<bool> {
+        let mut command = self.engine.command();
+        command
+            .arg("cp")
+            .arg(source)
+            .arg(format!("{}:{}", self.container_id()?, destination));
+
+        print_command(&mut command);
+
+        Ok(command.status()?.success())
+    }
+
+    fn exec(&self, exec: &Exec) -> Result<i32> {
+        let mut command = exec_command(
+            &self.engine,
+            &self.container_id()?,
+            &self.user,
+            &self.workspace_folder,
+            exec,
+        );
+
+        print_command(&mut command);
+
+        Ok(exit_code(command.status()?))
+    }
+
+    fn exec_output(&self, exec: &Exec) -> Result<String> {
+        let output = exec_command(
+            &self.engine,
+            &self.container_id()?,
+            &self.user,
+            &self.workspace_folder,
+            exec,
+        )
+        .stderr(Stdio::null())
+        .output()?;
+
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+
+    /// Compose interleaves the services' output and prefixes each line with
+    /// its service.
+    fn logs(&self, logs: &Logs) -> Result<bool> {
+        let docker_override = self.create_docker_compose()?;
+
+        let mut command = self.compose_command(Some(&docker_override));
+        command.arg("logs").args(logs.args());
+
+        if let Some(service) = &logs.service {
+            command.arg(service);
+        }
+
+        print_command(&mut command);
+
+        Ok(command.status()?.success())
+    }
+
+    fn metadata(&self) -> Result<Option<String>> {
+        let image = match self.primary()?.and_then(|service| self.image(&service)) {
+            Some(image) => image,
+            None => return Ok(None),
+        };
+
+        let mut command = self.engine.command();
+        command.arg("image").arg("inspect");
+
+        inspect_label(command.arg(image), METADATA_LABEL)
+    }
+
+    /// Builds the primary service again with the label added to its build
+    /// section, which only takes the label step when the rest is cached.
+    fn label(&self, metadata: String) -> Result<bool> {
+        // Images devcon didn't build aren't its own to relabel
+        if self
+            .primary()?
+            .is_none_or(|service| service.build.is_none())
+        {
+            return Ok(true);
+        }
+
+        let service = ServiceOverride {
+            build: Some(BuildOverride {
+                labels: [(METADATA_LABEL.to_string(), metadata)].into(),
+            }),
+            ..Default::default()
+        };
+        let mut label_override = ComposeOverride::default();
+        label_override
+            .services
+            .insert(self.service.clone(), service);
+
+        let file = settings::cache_dir()
+            .join("compose")
+            .join(&self.name)
+            .join("docker-compose.label.yml");
+        label_override.write(&file)?;
+
+        let docker_override = self.create_docker_compose()?;
+        let mut command = self.compose_command(Some(&docker_override));
+        command.arg("-f").arg(&file).arg("build");
+
+        for (key, value) in &self.build_args {
+            command.arg("--build-arg").arg(format!("{}={}", key, value));
+        }
+        command.arg(&self.service);
+
+        print_command(&mut command);
+
+        Ok(command.status()?.success())
+    }
+
+    fn container_label(&self, label: &str) -> Result<Option<String>> {
+        let id = match self.container_ids(true, true)?.into_iter().next() {
+            Some(id) => id,
+            None => return Ok(None),
+        };
+
+        let mut command = self.engine.command();
+        command.arg("inspect");
+
+        inspect_label(command.arg(id), label)
+    }
+
+    fn sync(&self) -> Result<bool> {
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Compose;
+    use crate::provider::engine::Engine;
+    use crate::provider::Labels;
+    use std::cell::OnceCell;
+
+    fn compose(labels: Labels) -> Compose {
+        Compose {
+            build_args: Default::default(),
+            cap_add: vec![],
+            container_user: None,
+            docker_override: OnceCell::new(),
+            engine: Engine::docker(),
+            env: [("A".to_string(), "1".to_string())].into(),
+            files: vec![],
+            forward_ports: [("app".to_string(), vec![3000])].into(),
+            labels,
+            mounts: vec![],
+            name: "devcon-app-12345678".to_string(),
+            override_command: true,
+            run_services: vec![],
+            security_opt: vec![],
+            service: "app".to_string(),
+            user: "root".to_string(),
+            workspace_folder: "/workspace".to_string(),
+        }
+    }
+
+    #[test]
+    fn renders_the_same_override_after_creating() {
+        let labels = Labels {
+            local_folder: "/src/app".to_string(),
+            config_file: "/src/app/.devcontainer/devcontainer.json".to_string(),
+            config_hash: "created".to_string(),
+            inputs_hash: Some("inputs".to_string()),
+            provider: "docker compose".to_string(),
+        };
+        let created = compose(labels.clone()).render().to_yaml().unwrap();
+
+        // A later run hashes the edited config and skips the inputs
+        let mut later = Labels {
+            config_hash: "edited".to_string(),
+            inputs_hash: None,
+            ..labels
+        };
+        later.keep_hashes(Some("created".to_string()), Some("inputs".to_string()));
+
+        assert_eq!(created, compose(later).render().to_yaml().unwrap());
+    }
+}
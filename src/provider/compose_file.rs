@@ -0,0 +1,264 @@
+use serde_yaml::Mapping;
+use serde_yaml::Value;
+use std::collections::BTreeMap;
+use std::io::Result;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// The subset of a compose service that devcon can run without a compose
+/// tool.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct Service {
+    pub name: String,
+    pub image: Option<String>,
+    pub build: Option<ServiceBuild>,
+    pub environment: BTreeMap<String, String>,
+    pub volumes: Vec<String>,
+    pub ports: Vec<String>,
+    pub depends_on: Vec<String>,
+    pub command: Option<Vec<String>>,
+    pub user: Option<String>,
+    pub working_dir: Option<String>,
+    pub cap_add: Vec<String>,
+    pub security_opt: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct ServiceBuild {
+    pub context: PathBuf,
+    pub dockerfile: Option<String>,
+    pub args: BTreeMap<String, String>,
+}
+
+/// Reads and merges compose files in order. Later files override scalars,
+/// merge mappings and extend sequences, as compose does. Relative paths are
+/// resolved against the directory of the first file.
+pub(crate) fn load(files: &[String], project: &str) -> Result<Vec<Service>> {
+    let mut merged = Value::Mapping(Mapping::new());
+
+    for file in files {
+        let contents = std::fs::read_to_string(file)?;
+        let value: Value = serde_yaml::from_str(&contents)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        merge(&mut merged, value);
+    }
+
+    let directory = files
+        .first()
+        .and_then(|file| Path::new(file).parent())
+        .map(|dir| dir.to_path_buf())
+        .unwrap_or_default();
+
+    let services = match merged.get("services") {
+        Some(Value::Mapping(services)) => services.clone(),
+        _ => Mapping::new(),
+    };
+
+    Ok(services
+        .iter()
+        .filter_map(|(name, service)| {
+            Some(parse_service(name.as_str()?, service, &directory, project))
+        })
+        .collect())
+}
+
+/// Orders services so that each comes after the services it depends on.
+/// Only `selected` services and their dependencies are returned; an empty
+/// selection means every service.
+pub(crate) fn ordered(services: &[Service], selected: &[String]) -> Vec<Service> {
+    fn visit(name: &str, services: &[Service], ordered: &mut Vec<Service>, seen: &mut Vec<String>) {
+        if seen.iter().any(|s| s == name) {
+            return;
+        }
+        seen.push(name.to_string());
+
+        if let Some(service) = services.iter().find(|s| s.name == name) {
+            for dependency in &service.depends_on {
+                visit(dependency, services, ordered, seen);
+            }
+            ordered.push(service.clone());
+        }
+    }
+
+    let mut ordered = vec![];
+    let mut seen = vec![];
+    let names: Vec<String> = if selected.is_empty() {
+        services.iter().map(|s| s.name.clone()).collect()
+    } else {
+        selected.to_vec()
+    };
+
+    for name in &names {
+        visit(name, services, &mut ordered, &mut seen);
+    }
+
+    ordered
+}
+
+fn merge(base: &mut Value, value: Value) {
+    match (base, value) {
+        (Value::Mapping(base), Value::Mapping(value)) => {
+            for (key, value) in value {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (Value::Sequence(base), Value::Sequence(value)) => {
+            for item in value {
+                if !base.contains(&item) {
+                    base.push(item);
+                }
+            }
+        }
+        (base, value) => *base = value,
+    }
+}
+
+fn parse_service(name: &str, value: &Value, directory: &Path, project: &str) -> Service {
+    let mut service = Service {
+        name: name.to_string(),
+        image: value.get("image").and_then(scalar),
+        user: value.get("user").and_then(scalar),
+        working_dir: value.get("working_dir").and_then(scalar),
+        cap_add: strings(value.get("cap_add")),
+        security_opt: strings(value.get("security_opt")),
+        ports: strings(value.get("ports")),
+        environment: key_values(value.get("environment")),
+        ..Default::default()
+    };
+
+    service.build = match value.get("build") {
+        Some(Value::String(context)) => Some(ServiceBuild {
+            context: directory.join(context),
+            dockerfile: None,
+            args: BTreeMap::new(),
+        }),
+        Some(build @ Value::Mapping(_)) => Some(ServiceBuild {
+            context: directory.join(build.get("context").and_then(scalar).unwrap_or_default()),
+            dockerfile: build.get("dockerfile").and_then(scalar),
+            args: key_values(build.get("args")),
+        }),
+        _ => None,
+    };
+
+    service.depends_on = match value.get("depends_on") {
+        Some(Value::Mapping(services)) => services.keys().filter_map(scalar).collect(),
+        other => strings(other),
+    };
+
+    service.command = match value.get("command") {
+        Some(Value::String(command)) => {
+            Some(command.split_whitespace().map(|s| s.to_string()).collect())
+        }
+        Some(Value::Sequence(_)) => Some(strings(value.get("command"))),
+        _ => None,
+    };
+
+    if let Some(Value::Sequence(volumes)) = value.get("volumes") {
+        service.volumes = volumes
+            .iter()
+            .filter_map(|volume| mount(volume, directory, project))
+            .collect();
+    }
+
+    service
+}
+
+/// Converts a short or long compose volume into a `--mount` spec.
+fn mount(volume: &Value, directory: &Path, project: &str) -> Option<String> {
+    let (kind, source, target, read_only) = match volume {
+        Value::String(spec) => {
+            let parts: Vec<&str> = spec.split(':').collect();
+            match parts.as_slice() {
+                [target] => ("volume".to_string(), None, target.to_string(), false),
+                [source, target, rest @ ..] => {
+                    let bind = source.starts_with('.')
+                        || source.starts_with('/')
+                        || source.starts_with('~');
+                    let kind = if bind { "bind" } else { "volume" };
+                    let read_only = rest.first().map(|m| m.contains("ro")).unwrap_or(false);
+                    (
+                        kind.to_string(),
+                        Some(source.to_string()),
+                        target.to_string(),
+                        read_only,
+                    )
+                }
+                [] => return None,
+            }
+        }
+        Value::Mapping(_) => (
+            volume
+                .get("type")
+                .and_then(scalar)
+                .unwrap_or_else(|| "volume".to_string()),
+            volume.get("source").and_then(scalar),
+            volume.get("target").and_then(scalar)?,
+            volume
+                .get("read_only")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false),
+        ),
+        _ => return None,
+    };
+
+    let source = source.map(|source| match kind.as_str() {
+        "bind" => {
+            let expanded = shellexpand::tilde(&source).to_string();
+            directory.join(expanded).to_string_lossy().to_string()
+        }
+        "volume" => format!("{}_{}", project, source),
+        _ => source,
+    });
+
+    let mut spec = format!("type={}", kind);
+    if let Some(source) = source {
+        spec.push_str(&format!(",source={}", source));
+    }
+    spec.push_str(&format!(",target={}", target));
+    if read_only {
+        spec.push_str(",readonly");
+    }
+
+    Some(spec)
+}
+
+fn scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+fn strings(value: Option<&Value>) -> Vec<String> {
+    match value {
+        Some(Value::Sequence(values)) => values.iter().filter_map(scalar).collect(),
+        _ => vec![],
+    }
+}
+
+/// Reads `environment` and `args` style values, which are either a mapping
+/// or a list of `KEY=value` strings.
+fn key_values(value: Option<&Value>) -> BTreeMap<String, String> {
+    match value {
+        Some(Value::Mapping(values)) => values
+            .iter()
+            .filter_map(|(key, value)| Some((scalar(key)?, scalar(value).unwrap_or_default())))
+            .collect(),
+        Some(Value::Sequence(values)) => values
+            .iter()
+            .filter_map(scalar)
+            .map(|entry| match entry.split_once('=') {
+                Some((key, value)) => (key.to_string(), value.to_string()),
+                None => (entry, String::new()),
+            })
+            .collect(),
+        _ => BTreeMap::new(),
+    }
+}
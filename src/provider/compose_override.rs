@@ -0,0 +1,101 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::Result;
+use std::path::Path;
+
+/// The compose file devcon layers on top of the project's compose files.
+#[derive(Debug, Default, Serialize)]
+pub(crate) struct ComposeOverride {
+    pub services: BTreeMap<String, ServiceOverride>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub(crate) struct ServiceOverride {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub build: Option<BuildOverride>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub environment: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub volumes: Vec<Volume>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub ports: Vec<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub cap_add: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub security_opt: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+}
+
+/// Merged into the service's own build section.
+#[derive(Debug, Default, Serialize)]
+pub(crate) struct BuildOverride {
+    pub labels: BTreeMap<String, String>,
+}
+
+/// A volume in compose's long syntax.
+#[derive(Debug, Serialize)]
+pub(crate) struct Volume {
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    pub target: String,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub read_only: bool,
+}
+
+impl Volume {
+    pub fn bind(source: &str, target: &str) -> Self {
+        Self {
+            kind: "bind".to_string(),
+            source: Some(source.to_string()),
+            target: target.to_string(),
+            read_only: false,
+        }
+    }
+
+    /// Converts a `--mount` style spec such as
+    /// `type=volume,source=cache,target=/cache` into a volume.
+    pub fn parse(spec: &str) -> Option<Self> {
+        let mut kind = "volume".to_string();
+        let mut source = None;
+        let mut target = None;
+        let mut read_only = false;
+
+        for part in spec.split(',') {
+            match part.split_once('=') {
+                Some(("type", value)) => kind = value.to_string(),
+                Some(("source" | "src", value)) => source = Some(value.to_string()),
+                Some(("target" | "destination" | "dst", value)) => target = Some(value.to_string()),
+                Some(("readonly" | "ro", value)) => read_only = value == "true" || value == "1",
+                None if part == "readonly" || part == "ro" => read_only = true,
+                _ => {}
+            }
+        }
+
+        Some(Self {
+            kind,
+            source,
+            target: target?,
+            read_only,
+        })
+    }
+}
+
+impl ComposeOverride {
+    pub fn to_yaml(&self) -> Result<String> {
+        serde_yaml::to_string(self)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn write(&self, file: &Path) -> Result<()> {
+        std::fs::write(file, self.to_yaml()?)
+    }
+}
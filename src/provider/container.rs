@@ -0,0 +1,320 @@
+use std::collections::HashMap;
+use std::env;
+use std::io::Result;
+use std::process::Stdio;
+
+use super::engine::Engine;
+use super::exec_command;
+use super::exit_code;
+use super::filter_labels;
+use super::inspect_label;
+use super::print_command;
+use super::relabel;
+use super::Exec;
+use super::Labels;
+use super::Logs;
+use super::Provider;
+use super::KEEP_ALIVE;
+use super::METADATA_LABEL;
+
+/// A single container built from a Dockerfile.
+#[derive(Debug)]
+pub struct Container {
+    pub build_args: HashMap<String, String>,
+    pub directory: String,
+    pub engine: Engine,
+    pub file: String,
+    pub forward_ports: Vec<u16>,
+    pub labels: Labels,
+    pub name: String,
+    pub run_args: Vec<String>,
+    pub user: String,
+    pub workspace_folder: String,
+}
+
+impl Provider for Container {
+    fn build(&self, use_cache: bool) -> Result<bool> {
+        let tag = format!("{}/{}", "devcon", &self.name);
+
+        let mut command = self.engine.command();
+        command
+            .arg("build")
+            .arg("-t")
+            .arg(&tag)
+            .arg("-f")
+            .arg(&self.file);
+
+        if !use_cache {
+            command.arg("--no-cache");
+        }
+
+        for (key, value) in &self.build_args {
+            command.arg("--build-arg").arg(format!("{}={}", key, value));
+        }
+
+        command.arg(&self.directory);
+
+        print_command(&mut command);
+
+        Ok(command.status()?.success())
+    }
+
+    fn create(&self, args: Vec<String>) -> Result<bool> {
+        let tag = format!("{}/{}", "devcon", &self.name);
+
+        let mut command = self.engine.command();
+        command.arg("create");
+        command.arg("--mount");
+
+        // A remote engine can't see local paths, so the workspace lives in a
+        // volume that is synced instead
+        if self.engine.remote {
+            command.arg(format!(
+                "type=volume,source={}-workspace,target={}",
+                &self.name, &self.workspace_folder
+            ));
+        } else {
+            command.arg(format!(
+                "type=bind,source={},target={}",
+                &self.directory, &self.workspace_folder
+            ));
+        }
+
+        // Forwards the ssh-agent to the container
+        if let Some(ssh_auth_sock) = env::var("SSH_AUTH_SOCK")
+            .ok()
+            .filter(|_| !self.engine.remote)
+        {
+            command.arg("--volume");
+            command.arg(format!("{}:/ssh-agent", ssh_auth_sock));
+            command.arg("--env");
+            command.arg("SSH_AUTH_SOCK=/ssh-agent");
+        }
+
+        for port in &self.forward_ports {
+            command.arg("--publish").arg(format!("{}:{}", port, port));
+        }
+
+        for (key, value) in self.labels.all() {
+            command.arg("--label").arg(format!("{}={}", key, value));
+        }
+
+        for arg in &self.engine.create_args {
+            command.arg(arg);
+        }
+
+        for arg in &args {
+            command.arg(arg);
+        }
+
+        for arg in &self.run_args {
+            command.arg(arg);
+        }
+
+        command.arg("--name");
+        command.arg(&self.name);
+        command.arg("-u");
+        command.arg(&self.user);
+        command.arg("-w");
+        command.arg(&self.workspace_folder);
+        command.arg(tag);
+        command.arg("sh").arg("-c").arg(KEEP_ALIVE);
+
+        print_command(&mut command);
+
+        Ok(command.status()?.success())
+    }
+
+    fn start(&self) -> Result<bool> {
+        let mut command = self.engine.command();
+        command.arg("start").arg(&self.name);
+
+        print_command(&mut command);
+
+        Ok(command.status()?.success())
+    }
+
+    fn stop(&self) -> Result<bool> {
+        let mut command = self.engine.command();
+        command.arg("stop").arg(&self.name);
+
+        print_command(&mut command);
+
+        Ok(command.status()?.success())
+    }
+
+    fn stop_container(&self) -> Result<bool> {
+        self.stop()
+    }
+
+    fn rm(&self) -> Result<bool> {
+        let mut command = self.engine.command();
+        command.arg("rm").arg(&self.name);
+
+        print_command(&mut command);
+
+        Ok(command.status()?.success())
+    }
+
+    fn down(&self, volumes: bool, images: bool) -> Result<bool> {
+        let mut command = self.engine.command();
+        command.arg("rm").arg("--force");
+        if volumes {
+            command.arg("--volumes");
+        }
+        command.arg(&self.name);
+
+        print_command(&mut command);
+
+        let mut success = command.status()?.success();
+
+        // The synced workspace of a remote engine is a named volume
+        if volumes && self.engine.remote {
+            let mut command = self.engine.command();
+            command
+                .arg("volume")
+                .arg("rm")
+                .arg(format!("{}-workspace", &self.name));
+
+            print_command(&mut command);
+
+            success &= command.status()?.success();
+        }
+
+        if images {
+            let mut command = self.engine.command();
+            command
+                .arg("rmi")
+                .arg(format!("{}/{}", "devcon", &self.name));
+
+            print_command(&mut command);
+
+            success &= command.status()?.success();
+        }
+
+        Ok(success)
+    }
+
+    fn exists(&self) -> Result<bool> {
+        let mut command = self.engine.command();
+        command.arg("ps").arg("-aq");
+
+        let output = filter_labels(&mut command, &self.labels).output()?.stdout;
+
+        let value = String::from_utf8(output).unwrap().trim().to_string();
+
+        Ok(!value.is_empty())
+    }
+
+    fn running(&self) -> Result<bool> {
+        let mut command = self.engine.command();
+        command.arg("ps").arg("-q");
+
+        let output = filter_labels(&mut command, &self.labels).output()?.stdout;
+
+        let value = String::from_utf8(output).unwrap().trim().to_string();
+
+        Ok(!value.is_empty())
+    }
+
+    fn id(&self) -> Result<Option<String>> {
+        let mut command = self.engine.command();
+        command.arg("ps").arg("-aq");
+
+        let output = filter_labels(&mut command, &self.labels).output()?.stdout;
+
+        Ok(String::from_utf8_lossy(&output)
+            .lines()
+            .next()
+            .map(|id| id.to_string()))
+    }
+
+    fn cp(&self, source: String, destination: String) -> Result<bool> {
+        let mut command = self.engine.command();
+        command
+            .arg("cp")
+            .arg(source)
+            .arg(format!("{}:{}", &self.name, destination));
+
+        print_command(&mut command);
+
+        Ok(command.status()?.success())
+    }
+
+    fn exec(&self, exec: &Exec) -> Result<i32> {
+        let mut command = exec_command(
+            &self.engine,
+            &self.name,
+            &self.user,
+            &self.workspace_folder,
+            exec,
+        );
+
+        print_command(&mut command);
+
+        Ok(exit_code(command.status()?))
+    }
+
+    fn exec_output(&self, exec: &Exec) -> Result<String> {
+        let output = exec_command(
+            &self.engine,
+            &self.name,
+            &self.user,
+            &self.workspace_folder,
+            exec,
+        )
+        .stderr(Stdio::null())
+        .output()?;
+
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+
+    fn logs(&self, logs: &Logs) -> Result<bool> {
+        let mut command = self.engine.command();
+        command.arg("logs").args(logs.args()).arg(&self.name);
+
+        print_command(&mut command);
+
+        Ok(command.status()?.success())
+    }
+
+    fn metadata(&self) -> Result<Option<String>> {
+        let tag = format!("{}/{}", "devcon", &self.name);
+
+        let mut command = self.engine.command();
+        command.arg("image").arg("inspect");
+
+        inspect_label(command.arg(&tag), METADATA_LABEL)
+    }
+
+    fn label(&self, metadata: String) -> Result<bool> {
+        relabel(
+            &self.engine,
+            &format!("{}/{}", "devcon", &self.name),
+            &metadata,
+        )
+    }
+
+    fn container_label(&self, label: &str) -> Result<Option<String>> {
+        let mut command = self.engine.command();
+        command.arg("container").arg("inspect");
+
+        inspect_label(command.arg(&self.name), label)
+    }
+
+    fn sync(&self) -> Result<bool> {
+        if !self.engine.remote {
+            return Ok(true);
+        }
+
+        let mut command = self.engine.command();
+        command
+            .arg("cp")
+            .arg(format!("{}/.", &self.directory))
+            .arg(format!("{}:{}", &self.name, &self.workspace_folder));
+
+        print_command(&mut command);
+
+        Ok(command.status()?.success())
+    }
+}
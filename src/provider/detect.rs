@@ -0,0 +1,100 @@
+use std::process::Command;
+use std::process::Stdio;
+
+use super::engine::ComposeStyle;
+use super::engine::Engine;
+use super::print_verbose;
+use crate::error::DevconError;
+use crate::settings::Provider;
+
+struct Candidate {
+    provider: Provider,
+    engine: Engine,
+    running: bool,
+    compose: bool,
+}
+
+/// Picks an installed engine for `provider = "auto"`. Engines whose daemon
+/// answers are preferred, then ones that can run compose projects when the
+/// project needs it, then docker over podman over nerdctl.
+pub fn provider(needs_compose: bool) -> Result<Provider, DevconError> {
+    let candidates: Vec<Candidate> = [
+        (Provider::Docker, Engine::docker()),
+        (Provider::Podman, Engine::podman()),
+        (Provider::Nerdctl, Engine::nerdctl()),
+    ]
+    .into_iter()
+    .filter_map(|(provider, engine)| probe(provider, engine))
+    .collect();
+
+    // The first candidate with the best score wins, keeping the order above
+    let score = |c: &Candidate| (c.running, !needs_compose || c.compose);
+    let best = candidates.iter().map(score).max();
+    let chosen = best.and_then(|best| candidates.iter().find(|c| score(c) == best));
+
+    match chosen {
+        Some(candidate) => {
+            let mut reasons = vec![if candidate.running {
+                "its daemon is running"
+            } else {
+                "it is installed, but no engine daemon is running"
+            }];
+            if needs_compose {
+                reasons.push(if candidate.compose {
+                    "it can run compose projects"
+                } else {
+                    "no installed engine can run compose projects"
+                });
+            }
+
+            print_verbose(&format!(
+                "Using {} because {}",
+                &candidate.engine.command,
+                reasons.join(" and ")
+            ));
+
+            Ok(candidate.provider.clone())
+        }
+        None => Err(DevconError::EngineNotFound(None)),
+    }
+}
+
+fn probe(provider: Provider, engine: Engine) -> Option<Candidate> {
+    if !succeeds(engine.command().arg("--version")) {
+        print_verbose(&format!("{} is not installed", &engine.command));
+        return None;
+    }
+
+    let running = succeeds(engine.command().arg("info"));
+    let compose = match &engine.compose {
+        ComposeStyle::Plugin => succeeds(engine.compose_command().arg("version")),
+        ComposeStyle::Standalone(_) => succeeds(engine.compose_command().arg("--version")),
+    };
+
+    print_verbose(&format!(
+        "{} is installed, daemon {}, compose {}",
+        &engine.command,
+        if running { "running" } else { "not running" },
+        if compose {
+            "available"
+        } else {
+            "not available"
+        }
+    ));
+
+    Some(Candidate {
+        provider,
+        engine,
+        running,
+        compose,
+    })
+}
+
+fn succeeds(command: &mut Command) -> bool {
+    command
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map(|status| status.success())
+        .unwrap_or(false)
+}
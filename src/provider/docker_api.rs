@@ -0,0 +1,807 @@
+use serde_json::json;
+use serde_json::Map;
+use serde_json::Value;
+use std::env;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Read;
+use std::io::Result;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+use super::container::Container;
+use super::dockerignore::context_paths;
+use super::dockerignore::DockerIgnore;
+use super::http::encode;
+use super::http::Client;
+use super::print_request;
+use super::progress;
+use super::Exec;
+use super::Logs;
+use super::Provider;
+use super::CONFIG_HASH_LABEL;
+use super::KEEP_ALIVE;
+use super::METADATA_LABEL;
+use crate::time;
+
+/// A single container driven through the Docker Engine API instead of the
+/// CLI. Attaching needs a raw terminal, so it is left to the CLI.
+#[derive(Debug)]
+pub struct DockerApi {
+    /// Describes the container, and runs what needs a raw terminal.
+    pub cli: Container,
+    pub client: Client,
+}
+
+impl DockerApi {
+    fn tag(&self) -> String {
+        format!("{}/{}", "devcon", &self.cli.name)
+    }
+
+    fn post(&self, path: &str) -> Result<bool> {
+        print_request("POST", path);
+
+        let response = self.client.request("POST", path, None, &[])?;
+
+        // 304 means the container already was in the requested state
+        Ok(response.is_success() || response.status == 304)
+    }
+
+    fn delete(&self, path: &str) -> Result<bool> {
+        print_request("DELETE", path);
+
+        let response = self.client.request("DELETE", path, None, &[])?;
+
+        // 404 means it already was removed
+        Ok(response.is_success() || response.status == 404)
+    }
+
+    /// Sends a build context and streams the build output as it arrives.
+    fn build_image(
+        &self,
+        query: &str,
+        context: impl FnOnce(&mut dyn Write) -> Result<()>,
+    ) -> Result<bool> {
+        let path = format!("/build?{}", query);
+        print_request("POST", &path);
+
+        let response = self
+            .client
+            .request_stream("POST", &path, "application/x-tar", context)?;
+        if !response.is_success() {
+            return Err(response.error());
+        }
+
+        let mut output = progress();
+        let mut success = true;
+        for line in BufReader::new(response.body()).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let message: Value = serde_json::from_str(&line)?;
+            if let Some(stream) = message["stream"].as_str() {
+                write!(output, "{}", stream)?;
+            } else if let Some(status) = message["status"].as_str() {
+                writeln!(output, "{}", status)?;
+            } else if let Some(error) = message["error"].as_str() {
+                eprintln!("{}", error);
+                success = false;
+            }
+        }
+
+        Ok(success)
+    }
+
+    /// Translates the CLI style create arguments into the API's container
+    /// configuration.
+    fn container_config(&self, args: &[String]) -> Result<Value> {
+        let mut env = vec![];
+        let mut binds = vec![];
+        let mut mounts = vec![json!({
+            "Type": "bind",
+            "Source": &self.cli.directory,
+            "Target": &self.cli.workspace_folder,
+        })];
+        let mut cap_add = vec![];
+        let mut cap_drop = vec![];
+        let mut security_opt = vec![];
+        let mut ports = vec![];
+        let mut labels: Map<String, Value> = self
+            .cli
+            .labels
+            .all()
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), json!(value)))
+            .collect();
+        let mut working_dir = self.cli.workspace_folder.clone();
+        let mut user = self.cli.user.clone();
+        let mut host_config = Map::new();
+
+        // Forwards the ssh-agent to the container
+        if let Ok(ssh_auth_sock) = env::var("SSH_AUTH_SOCK") {
+            binds.push(format!("{}:/ssh-agent", ssh_auth_sock));
+            env.push("SSH_AUTH_SOCK=/ssh-agent".to_string());
+        }
+
+        for port in &self.cli.forward_ports {
+            ports.push(format!("{}:{}", port, port));
+        }
+
+        // runArgs are already part of the arguments devcon passes in
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            let (flag, inline) = match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => (flag, Some(value.to_string())),
+                _ => (arg.as_str(), None),
+            };
+
+            // Switches only take their value inline, as in --init=false
+            let switch = inline.as_deref() != Some("false");
+            let mut value = || {
+                inline
+                    .clone()
+                    .or_else(|| iter.next().cloned())
+                    .ok_or_else(|| invalid(format!("Missing value for {}", flag)))
+            };
+            let mut host = |key: &str, value: Value| host_config.insert(key.to_string(), value);
+
+            match flag {
+                "-e" | "--env" => env.push(value()?),
+                "-v" | "--volume" => binds.push(value()?),
+                "--mount" => mounts.push(mount(&value()?)),
+                "-p" | "--publish" => ports.push(value()?),
+                "--cap-add" => cap_add.push(value()?),
+                "--cap-drop" => cap_drop.push(value()?),
+                "--security-opt" => security_opt.push(value()?),
+                "-w" | "--workdir" => working_dir = value()?,
+                "-u" | "--user" => user = value()?,
+                "-l" | "--label" => {
+                    let label = value()?;
+                    let (key, value) = label.split_once('=').unwrap_or((&label, ""));
+                    labels.insert(key.to_string(), json!(value));
+                }
+                "--init" => _ = host("Init", json!(switch)),
+                "--privileged" => _ = host("Privileged", json!(switch)),
+                "--network" | "--net" => _ = host("NetworkMode", json!(value()?)),
+                "--ipc" => _ = host("IpcMode", json!(value()?)),
+                "--pid" => _ = host("PidMode", json!(value()?)),
+                "--userns" => _ = host("UsernsMode", json!(value()?)),
+                "--add-host" => {
+                    let hosts = host_config.entry("ExtraHosts").or_insert_with(|| json!([]));
+                    hosts.as_array_mut().unwrap().push(json!(value()?));
+                }
+                "--device" => {
+                    let device = value()?;
+                    let mut parts = device.splitn(3, ':');
+                    let source = parts.next().unwrap_or_default();
+                    let target = parts.next().unwrap_or(source);
+                    let permissions = parts.next().unwrap_or("rwm");
+
+                    let devices = host_config.entry("Devices").or_insert_with(|| json!([]));
+                    devices.as_array_mut().unwrap().push(json!({
+                        "PathOnHost": source,
+                        "PathInContainer": target,
+                        "CgroupPermissions": permissions,
+                    }));
+                }
+                _ => {
+                    return Err(invalid(format!(
+                        "{} is not supported by the docker-api provider",
+                        arg
+                    )))
+                }
+            }
+        }
+
+        let mut exposed = Map::new();
+        let mut bindings = Map::new();
+        for port in ports {
+            let (host, container) = port.rsplit_once(':').unwrap_or((&port, &port));
+            let container = if container.contains('/') {
+                container.to_string()
+            } else {
+                format!("{}/tcp", container)
+            };
+
+            exposed.insert(container.clone(), json!({}));
+            bindings.insert(container, json!([{ "HostPort": host }]));
+        }
+
+        host_config.insert("Binds".to_string(), json!(binds));
+        host_config.insert("Mounts".to_string(), json!(mounts));
+        host_config.insert("PortBindings".to_string(), json!(bindings));
+        host_config.insert("CapAdd".to_string(), json!(cap_add));
+        host_config.insert("CapDrop".to_string(), json!(cap_drop));
+        host_config.insert("SecurityOpt".to_string(), json!(security_opt));
+
+        Ok(json!({
+            "Image": self.tag(),
+            "Cmd": ["sh", "-c", KEEP_ALIVE],
+            "User": user,
+            "WorkingDir": working_dir,
+            "Env": env,
+            "Labels": labels,
+            "ExposedPorts": exposed,
+            "HostConfig": host_config,
+        }))
+    }
+
+    /// Runs a command through the API, writing its output to `stdout`, and
+    /// returns its exit code.
+    fn run_exec(&self, exec: &Exec, stdout: &mut dyn Write) -> Result<i32> {
+        let path = format!("/containers/{}/exec", &self.cli.name);
+        print_request("POST", &path);
+
+        let env: Vec<String> = exec
+            .env
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect();
+
+        let response = self.client.post_json(
+            &path,
+            &json!({
+                "User": &self.cli.user,
+                "WorkingDir": &self.cli.workspace_folder,
+                "Cmd": &exec.args,
+                "Env": env,
+                "AttachStdout": true,
+                "AttachStderr": true,
+            }),
+        )?;
+        if !response.is_success() {
+            return Err(response.error());
+        }
+
+        let id = response.json()?["Id"]
+            .as_str()
+            .map(|id| id.to_string())
+            .ok_or_else(|| invalid("No exec id returned".to_string()))?;
+
+        let response = self.client.post_json(
+            &format!("/exec/{}/start", id),
+            &json!({ "Detach": false, "Tty": false }),
+        )?;
+        if !response.is_success() {
+            return Err(response.error());
+        }
+
+        demultiplex(response.body(), stdout)?;
+
+        let exec = self.client.get(&format!("/exec/{}/json", id))?.json()?;
+
+        Ok(exec["ExitCode"].as_i64().unwrap_or(1) as i32)
+    }
+
+    /// Finds the project's container by its labels.
+    fn find(&self) -> Result<Option<Value>> {
+        let filters = serde_json::to_string(&json!({ "label": self.cli.labels.filters() }))?;
+
+        let response = self.client.get(&format!(
+            "/containers/json?all=true&filters={}",
+            encode(&filters)
+        ))?;
+        if !response.is_success() {
+            return Err(response.error());
+        }
+
+        Ok(response.json()?.as_array().and_then(|c| c.first().cloned()))
+    }
+}
+
+impl Provider for DockerApi {
+    fn build(&self, use_cache: bool) -> Result<bool> {
+        let directory = Path::new(&self.cli.directory);
+        let paths = context_paths(directory, &DockerIgnore::read(directory))?;
+
+        // The Dockerfile has to be part of the context, even when ignored
+        let dockerfile = match Path::new(&self.cli.file).strip_prefix(directory) {
+            Ok(relative) => relative.to_path_buf(),
+            Err(_) => PathBuf::from(".devcon.Dockerfile"),
+        };
+        let add_dockerfile = !paths.contains(&dockerfile);
+        let dockerfile_name = dockerfile
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        let build_args = serde_json::to_string(&self.cli.build_args)?;
+        let query = format!(
+            "t={}&dockerfile={}&nocache={}&buildargs={}",
+            encode(&self.tag()),
+            encode(&dockerfile_name),
+            !use_cache,
+            encode(&build_args)
+        );
+
+        self.build_image(&query, |body| {
+            let mut context = tar::Builder::new(body);
+            context.follow_symlinks(false);
+
+            for path in &paths {
+                context.append_path_with_name(directory.join(path), path)?;
+            }
+            if add_dockerfile {
+                context.append_path_with_name(&self.cli.file, &dockerfile)?;
+            }
+
+            context.into_inner().map(|_| ())
+        })
+    }
+
+    fn create(&self, args: Vec<String>) -> Result<bool> {
+        let path = format!("/containers/create?name={}", encode(&self.cli.name));
+        print_request("POST", &path);
+
+        let response = self
+            .client
+            .post_json(&path, &self.container_config(&args)?)?;
+        if !response.is_success() {
+            return Err(response.error());
+        }
+
+        Ok(true)
+    }
+
+    fn start(&self) -> Result<bool> {
+        self.post(&format!("/containers/{}/start", &self.cli.name))
+    }
+
+    fn stop(&self) -> Result<bool> {
+        self.post(&format!("/containers/{}/stop", &self.cli.name))
+    }
+
+    fn stop_container(&self) -> Result<bool> {
+        self.stop()
+    }
+
+    fn rm(&self) -> Result<bool> {
+        let path = format!("/containers/{}", &self.cli.name);
+        print_request("DELETE", &path);
+
+        let response = self.client.request("DELETE", &path, None, &[])?;
+
+        Ok(response.is_success())
+    }
+
+    fn down(&self, volumes: bool, images: bool) -> Result<bool> {
+        let mut success = self.delete(&format!(
+            "/containers/{}?force=true&v={}",
+            &self.cli.name, volumes
+        ))?;
+
+        if images {
+            success &= self.delete(&format!("/images/{}", encode(&self.tag())))?;
+        }
+
+        Ok(success)
+    }
+
+    fn exists(&self) -> Result<bool> {
+        Ok(self.find()?.is_some())
+    }
+
+    fn running(&self) -> Result<bool> {
+        Ok(self
+            .find()?
+            .map(|container| container["State"] == "running")
+            .unwrap_or(false))
+    }
+
+    fn id(&self) -> Result<Option<String>> {
+        Ok(self
+            .find()?
+            .and_then(|container| container["Id"].as_str().map(|id| id.to_string())))
+    }
+
+    fn cp(&self, source: String, destination: String) -> Result<bool> {
+        let source = Path::new(&source);
+        let destination = Path::new(&destination);
+
+        // Directories are copied into the destination, files are copied to it
+        let mut archive = tar::Builder::new(vec![]);
+        let target = if source.is_dir() {
+            let name = source.file_name().unwrap_or_default();
+            archive.append_dir_all(name, source)?;
+            destination.to_path_buf()
+        } else {
+            let name = destination.file_name().unwrap_or_default();
+            archive.append_path_with_name(source, name)?;
+            destination.parent().unwrap_or(Path::new("/")).to_path_buf()
+        };
+
+        let path = format!(
+            "/containers/{}/archive?path={}",
+            &self.cli.name,
+            encode(&target.to_string_lossy())
+        );
+        print_request("PUT", &path);
+
+        let response = self.client.request(
+            "PUT",
+            &path,
+            Some("application/x-tar"),
+            &archive.into_inner()?,
+        )?;
+
+        Ok(response.is_success())
+    }
+
+    fn exec(&self, exec: &Exec) -> Result<i32> {
+        // Stdin and terminals need a raw connection, so those are left to
+        // the CLI
+        if exec.interactive || exec.tty {
+            return self.cli.exec(exec);
+        }
+
+        self.run_exec(exec, &mut progress())
+    }
+
+    fn exec_output(&self, exec: &Exec) -> Result<String> {
+        let mut output = vec![];
+        self.run_exec(exec, &mut output)?;
+
+        Ok(String::from_utf8_lossy(&output).to_string())
+    }
+
+    fn logs(&self, logs: &Logs) -> Result<bool> {
+        let mut path = format!(
+            "/containers/{}/logs?stdout=true&stderr=true&follow={}",
+            &self.cli.name, logs.follow
+        );
+        if let Some(since) = &logs.since {
+            path.push_str(&format!("&since={}", since_timestamp(since)?));
+        }
+        print_request("GET", &path);
+
+        let response = self.client.get(&path)?;
+        if !response.is_success() {
+            return Err(response.error());
+        }
+
+        demultiplex(response.body(), &mut std::io::stdout())?;
+
+        Ok(true)
+    }
+
+    fn metadata(&self) -> Result<Option<String>> {
+        let response = self.client.get(&format!("/images/{}/json", self.tag()))?;
+        if !response.is_success() {
+            return Ok(None);
+        }
+
+        let image = response.json()?;
+
+        Ok(image["Config"]["Labels"][METADATA_LABEL]
+            .as_str()
+            .map(|label| label.to_string()))
+    }
+
+    fn label(&self, metadata: String) -> Result<bool> {
+        // Rebuilds the image on top of itself with the merged metadata label,
+        // since labels cannot be added to an existing image.
+        let dockerfile = format!("FROM {}\n", self.tag());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(dockerfile.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+
+        let labels = serde_json::to_string(&json!({ METADATA_LABEL: metadata }))?;
+        let query = format!("t={}&labels={}", encode(&self.tag()), encode(&labels));
+
+        self.build_image(&query, |body| {
+            let mut context = tar::Builder::new(body);
+            context.append_data(&mut header, "Dockerfile", dockerfile.as_bytes())?;
+
+            context.into_inner().map(|_| ())
+        })
+    }
+
+    fn container_label(&self, label: &str) -> Result<Option<String>> {
+        Ok(self
+            .find()?
+            .and_then(|container| container["Labels"][label].as_str().map(|l| l.to_string())))
+    }
+
+    fn sync(&self) -> Result<bool> {
+        Ok(true)
+    }
+}
+
+/// Inspects every container devcon created, whichever project it belongs to.
+pub(crate) fn inspect_all(client: &Client) -> Result<Vec<Value>> {
+    let filters = serde_json::to_string(&json!({ "label": [CONFIG_HASH_LABEL] }))?;
+
+    let response = client.get(&format!(
+        "/containers/json?all=true&filters={}",
+        encode(&filters)
+    ))?;
+    if !response.is_success() {
+        return Err(response.error());
+    }
+
+    let mut containers = vec![];
+    for container in response.json()?.as_array().into_iter().flatten() {
+        let id = container["Id"].as_str().unwrap_or_default();
+        let response = client.get(&format!("/containers/{}/json", id))?;
+        if response.is_success() {
+            containers.push(response.json()?);
+        }
+    }
+
+    Ok(containers)
+}
+
+/// The API only takes a unix timestamp, where the CLI also takes durations
+/// and RFC 3339 times.
+fn since_timestamp(since: &str) -> Result<u64> {
+    if let Ok(timestamp) = since.parse() {
+        return Ok(timestamp);
+    }
+
+    time::duration(since)
+        .map(|duration| time::now().saturating_sub(duration))
+        .or_else(|| time::unix_time(since))
+        .ok_or_else(|| invalid(format!("Invalid time {}", since)))
+}
+
+/// Copies a multiplexed exec stream to stdout and stderr. Every frame starts
+/// with an 8 byte header holding the stream type and the payload size.
+fn demultiplex(mut body: impl Read, stdout: &mut dyn Write) -> Result<()> {
+    let mut header = [0u8; 8];
+
+    loop {
+        match body.read_exact(&mut header) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(()),
+            Err(e) => return Err(e),
+        }
+
+        let size = u32::from_be_bytes([header[4], header[5], header[6], header[7]]) as usize;
+        let mut payload = vec![0u8; size];
+        body.read_exact(&mut payload)?;
+
+        match header[0] {
+            2 => std::io::stderr().write_all(&payload)?,
+            _ => stdout.write_all(&payload)?,
+        }
+    }
+}
+
+fn mount(spec: &str) -> Value {
+    let mut mount = Map::new();
+    mount.insert("Type".to_string(), json!("volume"));
+
+    for part in spec.split(',') {
+        match part.split_once('=') {
+            Some(("type", value)) => {
+                mount.insert("Type".to_string(), json!(value));
+            }
+            Some(("source" | "src", value)) => {
+                mount.insert("Source".to_string(), json!(value));
+            }
+            Some(("target" | "destination" | "dst", value)) => {
+                mount.insert("Target".to_string(), json!(value));
+            }
+            Some(("readonly" | "ro", value)) => {
+                mount.insert(
+                    "ReadOnly".to_string(),
+                    json!(value == "true" || value == "1"),
+                );
+            }
+            None if part == "readonly" || part == "ro" => {
+                mount.insert("ReadOnly".to_string(), json!(true));
+            }
+            _ => {}
+        }
+    }
+
+    Value::Object(mount)
+}
+
+fn invalid(message: String) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidInput, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::demultiplex;
+    use super::DockerApi;
+    use crate::provider::container::Container;
+    use crate::provider::engine::Engine;
+    use crate::provider::http::fake;
+    use crate::provider::http::Client;
+    use crate::provider::Exec;
+    use crate::provider::Labels;
+    use crate::provider::Provider;
+    use std::collections::HashMap;
+    use std::path::Path;
+
+    fn frame(stream: u8, payload: &[u8]) -> Vec<u8> {
+        let mut frame = vec![stream, 0, 0, 0];
+        frame.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+        frame.extend_from_slice(payload);
+        frame
+    }
+
+    fn response(body: &[u8]) -> Vec<u8> {
+        let mut response =
+            format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n", body.len()).into_bytes();
+        response.extend_from_slice(body);
+        response
+    }
+
+    fn api(client: Client, directory: &Path) -> DockerApi {
+        let directory = directory.to_string_lossy().to_string();
+        let file = format!("{}/.devcontainer/Dockerfile", directory);
+        let labels = Labels {
+            local_folder: directory.clone(),
+            config_file: format!("{}/.devcontainer/devcontainer.json", directory),
+            config_hash: String::new(),
+            inputs_hash: None,
+            provider: "docker-api".to_string(),
+        };
+
+        DockerApi {
+            cli: Container {
+                build_args: HashMap::new(),
+                directory,
+                engine: Engine::docker(),
+                file,
+                forward_ports: vec![],
+                labels,
+                name: "devcon-test".to_string(),
+                run_args: vec![],
+                user: "root".to_string(),
+                workspace_folder: "/workspaces/test".to_string(),
+            },
+            client,
+        }
+    }
+
+    #[test]
+    fn demultiplexes_stdout() {
+        let mut body = frame(1, b"out ");
+        body.extend(frame(2, b"err"));
+        body.extend(frame(1, b"more"));
+
+        let mut stdout = vec![];
+        demultiplex(&body[..], &mut stdout).unwrap();
+
+        assert_eq!(stdout, b"out more");
+    }
+
+    #[test]
+    fn returns_the_exec_exit_code() {
+        let (client, server) = fake::serve("exec", 3, |request| {
+            if request
+                .line
+                .starts_with("POST /containers/devcon-test/exec")
+            {
+                response(br#"{"Id":"e1"}"#)
+            } else if request.line.starts_with("POST /exec/e1/start") {
+                response(&frame(1, b"hello\n"))
+            } else {
+                response(br#"{"ExitCode":3}"#)
+            }
+        });
+
+        let output = api(client, Path::new("/tmp"))
+            .exec_output(&Exec::shell("echo hello; exit 3".to_string()))
+            .unwrap();
+        assert_eq!(output, "hello\n");
+
+        let requests = server.join().unwrap();
+        assert_eq!(requests[2].line, "GET /exec/e1/json HTTP/1.1");
+
+        let (client, server) = fake::serve("exec-code", 3, |request| {
+            if request.line.starts_with("POST /containers") {
+                response(br#"{"Id":"e2"}"#)
+            } else if request.line.starts_with("POST /exec") {
+                response(b"")
+            } else {
+                response(br#"{"ExitCode":3}"#)
+            }
+        });
+
+        let code = api(client, Path::new("/tmp"))
+            .exec(&Exec::shell("exit 3".to_string()))
+            .unwrap();
+        assert_eq!(code, 3);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn builds_from_the_context_without_ignored_files() {
+        let directory = std::env::temp_dir().join(format!("devcon-context-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(directory.join(".devcontainer")).unwrap();
+        std::fs::create_dir_all(directory.join("target/debug")).unwrap();
+        std::fs::create_dir_all(directory.join("src")).unwrap();
+        std::fs::write(directory.join(".devcontainer/Dockerfile"), "FROM alpine\n").unwrap();
+        std::fs::write(
+            directory.join(".dockerignore"),
+            "target\n*.secret\n.devcontainer\n",
+        )
+        .unwrap();
+        std::fs::write(directory.join("target/debug/devcon"), "binary").unwrap();
+        std::fs::write(directory.join("api.secret"), "token").unwrap();
+        std::fs::write(directory.join("src/main.rs"), "fn main() {}").unwrap();
+
+        let (client, server) = fake::serve("build", 1, |_| {
+            b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n13\r\n{\"stream\":\"Done\\n\"}\r\n0\r\n\r\n"
+                .to_vec()
+        });
+
+        assert!(api(client, &directory).build(true).unwrap());
+
+        let requests = server.join().unwrap();
+        assert!(requests[0]
+            .line
+            .contains("dockerfile=.devcontainer%2FDockerfile"));
+
+        let mut archive = tar::Archive::new(&requests[0].body[..]);
+        let mut names: Vec<String> = archive
+            .entries()
+            .unwrap()
+            .map(|entry| entry.unwrap().path().unwrap().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+
+        // The Dockerfile is sent even though its directory is ignored
+        assert_eq!(
+            names,
+            [
+                ".devcontainer/Dockerfile",
+                ".dockerignore",
+                "src",
+                "src/main.rs"
+            ]
+        );
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn translates_run_args() {
+        let client = Client {
+            socket: "/nonexistent.sock".into(),
+        };
+        let args: Vec<String> = [
+            "--init",
+            "--privileged=false",
+            "--network=host",
+            "--add-host",
+            "db:10.0.0.2",
+            "--device=/dev/fuse",
+            "--label=team=tools",
+        ]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect();
+
+        let config = api(client, Path::new("/tmp"))
+            .container_config(&args)
+            .unwrap();
+        let host = &config["HostConfig"];
+
+        assert_eq!(host["Init"], true);
+        assert_eq!(host["Privileged"], false);
+        assert_eq!(host["NetworkMode"], "host");
+        assert_eq!(host["ExtraHosts"][0], "db:10.0.0.2");
+        assert_eq!(host["Devices"][0]["PathInContainer"], "/dev/fuse");
+        assert_eq!(config["Labels"]["team"], "tools");
+
+        let args = vec!["--gpus=all".to_string()];
+        let client = Client {
+            socket: "/nonexistent.sock".into(),
+        };
+        assert!(api(client, Path::new("/tmp"))
+            .container_config(&args)
+            .is_err());
+    }
+}
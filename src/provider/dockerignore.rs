@@ -0,0 +1,199 @@
+use std::io::Result;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// The patterns of a build context's .dockerignore, matched the way docker
+/// does: globs against the path relative to the context, where a pattern
+/// also covers everything under what it matches, the last matching pattern
+/// wins and `!` includes a path again.
+#[derive(Debug, Default)]
+pub(crate) struct DockerIgnore {
+    patterns: Vec<Pattern>,
+}
+
+#[derive(Debug)]
+struct Pattern {
+    segments: Vec<String>,
+    exclude: bool,
+}
+
+impl DockerIgnore {
+    /// Reads the context's .dockerignore, if it has one.
+    pub fn read(context: &Path) -> Self {
+        let contents = std::fs::read_to_string(context.join(".dockerignore")).unwrap_or_default();
+
+        Self::parse(&contents)
+    }
+
+    pub fn parse(contents: &str) -> Self {
+        let patterns = contents
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| {
+                let (exclude, pattern) = match line.strip_prefix('!') {
+                    Some(pattern) => (false, pattern.trim()),
+                    None => (true, line),
+                };
+                let segments: Vec<String> = split(pattern).map(|s| s.to_string()).collect();
+
+                (!segments.is_empty()).then_some(Pattern { segments, exclude })
+            })
+            .collect();
+
+        Self { patterns }
+    }
+
+    /// Whether a `/` separated path, relative to the context, is left out.
+    pub fn is_ignored(&self, path: &str) -> bool {
+        let path: Vec<&str> = split(path).collect();
+        let mut ignored = false;
+
+        for pattern in &self.patterns {
+            // A pattern matching a parent directory covers the path too
+            let matched = (1..=path.len()).any(|end| matches(&pattern.segments, &path[..end]));
+            if matched {
+                ignored = pattern.exclude;
+            }
+        }
+
+        ignored
+    }
+
+    /// Exceptions can include paths inside ignored directories, which then
+    /// have to be walked anyway.
+    pub fn has_exceptions(&self) -> bool {
+        self.patterns.iter().any(|pattern| !pattern.exclude)
+    }
+}
+
+/// The files, directories and symlinks of a build context that aren't
+/// ignored, relative to it and in a stable order.
+pub(crate) fn context_paths(context: &Path, ignore: &DockerIgnore) -> Result<Vec<PathBuf>> {
+    let mut paths = vec![];
+    walk(context, Path::new(""), ignore, &mut paths)?;
+
+    Ok(paths)
+}
+
+fn walk(
+    context: &Path,
+    relative: &Path,
+    ignore: &DockerIgnore,
+    paths: &mut Vec<PathBuf>,
+) -> Result<()> {
+    let mut entries: Vec<_> = std::fs::read_dir(context.join(relative))?
+        .map(|entry| entry.map(|entry| relative.join(entry.file_name())))
+        .collect::<Result<_>>()?;
+    entries.sort();
+
+    for entry in entries {
+        let ignored = ignore.is_ignored(&entry.to_string_lossy());
+        let path = context.join(&entry);
+
+        if path.is_dir() && !path.is_symlink() {
+            if !ignored {
+                paths.push(entry.clone());
+            }
+            if !ignored || ignore.has_exceptions() {
+                walk(context, &entry, ignore, paths)?;
+            }
+        } else if !ignored {
+            paths.push(entry);
+        }
+    }
+
+    Ok(())
+}
+
+/// Splits a path into its segments, dropping empty and `.` ones like
+/// docker's path cleaning does.
+fn split(path: &str) -> impl Iterator<Item = &str> {
+    path.split(['/', '\\'])
+        .filter(|segment| !segment.is_empty() && *segment != ".")
+}
+
+fn matches(pattern: &[String], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((first, rest)) if first == "**" => {
+            (0..=path.len()).any(|skip| matches(rest, &path[skip..]))
+        }
+        Some((first, rest)) => match path.split_first() {
+            Some((segment, path)) => {
+                glob(first.as_bytes(), segment.as_bytes()) && matches(rest, path)
+            }
+            None => false,
+        },
+    }
+}
+
+/// Matches one path segment against `*`, `?` and `[...]` globs.
+fn glob(pattern: &[u8], name: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some((b'*', rest)) => (0..=name.len()).any(|skip| glob(rest, &name[skip..])),
+        Some((b'?', rest)) => !name.is_empty() && glob(rest, &name[1..]),
+        Some((b'[', rest)) => {
+            let end = match rest.iter().position(|&c| c == b']') {
+                Some(end) => end,
+                None => return name.first() == Some(&b'[') && glob(rest, &name[1..]),
+            };
+            let (class, rest) = (&rest[..end], &rest[end + 1..]);
+
+            match name.split_first() {
+                Some((&c, name)) => in_class(class, c) && glob(rest, name),
+                None => false,
+            }
+        }
+        Some((b'\\', [escaped, rest @ ..])) => {
+            name.first() == Some(escaped) && glob(rest, &name[1..])
+        }
+        Some((c, rest)) => name.first() == Some(c) && glob(rest, &name[1..]),
+    }
+}
+
+fn in_class(class: &[u8], c: u8) -> bool {
+    let (negated, class) = match class.split_first() {
+        Some((b'^', class)) | Some((b'!', class)) => (true, class),
+        _ => (false, class),
+    };
+
+    let mut found = false;
+    let mut i = 0;
+    while i < class.len() {
+        if i + 2 < class.len() && class[i + 1] == b'-' {
+            found |= (class[i]..=class[i + 2]).contains(&c);
+            i += 3;
+        } else {
+            found |= class[i] == c;
+            i += 1;
+        }
+    }
+
+    found != negated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DockerIgnore;
+
+    #[test]
+    fn matches_like_docker() {
+        let ignore = DockerIgnore::parse(
+            "# comment\n/target\nnode_modules\n**/*.log\n*.env\n!keep.env\nsecrets/[a-c]*\n",
+        );
+
+        assert!(ignore.is_ignored("target"));
+        assert!(ignore.is_ignored("target/debug/devcon"));
+        assert!(ignore.is_ignored("node_modules/left-pad/index.js"));
+        assert!(!ignore.is_ignored("web/node_modules"));
+        assert!(ignore.is_ignored("logs/today.log"));
+        assert!(ignore.is_ignored("today.log"));
+        assert!(ignore.is_ignored("prod.env"));
+        assert!(!ignore.is_ignored("keep.env"));
+        assert!(ignore.is_ignored("secrets/api"));
+        assert!(!ignore.is_ignored("secrets/token"));
+        assert!(!ignore.is_ignored("src/main.rs"));
+    }
+}
@@ -0,0 +1,198 @@
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// How an engine runs compose projects.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ComposeStyle {
+    /// A subcommand of the engine binary, e.g. `docker compose`.
+    Plugin,
+    /// A separate binary, e.g. `podman-compose`.
+    Standalone(String),
+}
+
+/// Describes a container engine CLI. The providers are written once against
+/// this profile, so supporting another docker-compatible engine only means
+/// adding a profile.
+#[derive(Debug, Clone)]
+pub struct Engine {
+    pub command: String,
+    pub compose: ComposeStyle,
+    /// Extra flags for `create`, such as podman's rootless user mapping.
+    pub create_args: Vec<String>,
+    /// The flags of `create_args` that belong on `pod create` instead, since
+    /// a pod's containers share its user namespace.
+    pub pod_args: Vec<String>,
+    /// The variable the engine and its compose tool read a context from.
+    pub context_env: Option<String>,
+    /// The variable the engine and its compose tool read a host from.
+    pub host_env: String,
+    /// Set on every invocation to select a context or host.
+    pub env: Vec<(String, String)>,
+    /// Whether the engine runs on another machine, so local paths can't be
+    /// bind mounted.
+    pub remote: bool,
+}
+
+impl Engine {
+    pub fn docker() -> Self {
+        Self {
+            command: "docker".to_string(),
+            compose: ComposeStyle::Plugin,
+            create_args: vec![],
+            pod_args: vec![],
+            context_env: Some("DOCKER_CONTEXT".to_string()),
+            host_env: "DOCKER_HOST".to_string(),
+            env: vec![],
+            remote: false,
+        }
+    }
+
+    pub fn podman() -> Self {
+        Self {
+            command: "podman".to_string(),
+            compose: ComposeStyle::Standalone("podman-compose".to_string()),
+            create_args: vec![
+                "--userns=keep-id".to_string(),
+                "--security-opt".to_string(),
+                "label=disable".to_string(),
+            ],
+            pod_args: vec!["--userns=keep-id".to_string()],
+            context_env: Some("CONTAINER_CONNECTION".to_string()),
+            host_env: "CONTAINER_HOST".to_string(),
+            env: vec![],
+            remote: false,
+        }
+    }
+
+    pub fn nerdctl() -> Self {
+        Self {
+            command: "nerdctl".to_string(),
+            compose: ComposeStyle::Plugin,
+            create_args: vec![],
+            pod_args: vec![],
+            context_env: None,
+            host_env: "CONTAINERD_ADDRESS".to_string(),
+            env: vec![],
+            remote: false,
+        }
+    }
+
+    /// Points every invocation at a context or host. Either one falls back
+    /// to what is already set in the environment when deciding whether the
+    /// engine is remote.
+    pub fn connect(mut self, context: Option<String>, host: Option<String>) -> Self {
+        if let (Some(context), Some(context_env)) = (&context, &self.context_env) {
+            self.env.push((context_env.clone(), context.clone()));
+        }
+
+        if let Some(host) = &host {
+            self.env.push((self.host_env.clone(), host.clone()));
+        }
+
+        let host = host.or_else(|| std::env::var(&self.host_env).ok());
+        let context = context.or_else(|| {
+            let context_env = self.context_env.as_ref()?;
+            std::env::var(context_env).ok()
+        });
+
+        self.remote = match (host, context) {
+            (Some(host), _) if !host.is_empty() => is_remote(&host),
+            (_, Some(context)) if !context.is_empty() => self
+                .context_host(&context)
+                .map(|host| is_remote(&host))
+                .unwrap_or(true),
+            _ => false,
+        };
+
+        self
+    }
+
+    /// Looks up the endpoint a docker context connects to.
+    pub fn context_host(&self, context: &str) -> Option<String> {
+        let output = Command::new(&self.command)
+            .arg("context")
+            .arg("inspect")
+            .arg("--format")
+            .arg("{{.Endpoints.docker.Host}}")
+            .arg(context)
+            .output()
+            .ok()?;
+
+        if !output.status.success() {
+            return None;
+        }
+
+        Some(String::from_utf8(output.stdout).ok()?.trim().to_string())
+    }
+
+    /// The `create_args` for a container in a pod.
+    pub fn pod_container_args(&self) -> impl Iterator<Item = &String> {
+        self.create_args
+            .iter()
+            .filter(|arg| !self.pod_args.contains(arg))
+    }
+
+    pub fn command(&self) -> Command {
+        let mut command = Command::new(&self.command);
+        command.envs(self.env.iter().cloned());
+
+        command
+    }
+
+    pub fn compose_command(&self) -> Command {
+        match &self.compose {
+            ComposeStyle::Plugin => {
+                let mut command = self.command();
+                command.arg("compose");
+
+                command
+            }
+            ComposeStyle::Standalone(compose) => {
+                let mut command = Command::new(compose);
+                command.envs(self.env.iter().cloned());
+
+                command
+            }
+        }
+    }
+
+    /// The first binary the engine needs that isn't installed, checked
+    /// up front so it isn't reported as a bare "No such file or directory".
+    pub fn missing(&self, compose: bool) -> Option<String> {
+        let mut programs = vec![&self.command];
+        if let (true, ComposeStyle::Standalone(compose)) = (compose, &self.compose) {
+            programs.push(compose);
+        }
+
+        programs
+            .into_iter()
+            .find(|program| !installed(program))
+            .cloned()
+    }
+}
+
+fn installed(program: &str) -> bool {
+    let candidates = |path: PathBuf| {
+        let exe = path.with_extension(std::env::consts::EXE_EXTENSION);
+        [path, exe]
+    };
+
+    if Path::new(program).components().count() > 1 {
+        return candidates(PathBuf::from(program))
+            .iter()
+            .any(|path| path.is_file());
+    }
+
+    std::env::var_os("PATH")
+        .map(|paths| {
+            std::env::split_paths(&paths)
+                .flat_map(|dir| candidates(dir.join(program)))
+                .any(|path| path.is_file())
+        })
+        .unwrap_or(false)
+}
+
+fn is_remote(host: &str) -> bool {
+    !(host.starts_with("unix://") || host.starts_with("npipe://") || host.starts_with('/'))
+}
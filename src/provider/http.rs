@@ -0,0 +1,448 @@
+use serde_json::Value;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::BufWriter;
+use std::io::Read;
+use std::io::Result;
+use std::io::Write;
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+
+/// A minimal HTTP/1.1 client for the Docker Engine API on a Unix socket.
+/// The socket path is all it needs, so it works just as well against a
+/// fake server listening on a temporary socket.
+#[derive(Debug, Clone)]
+pub struct Client {
+    pub socket: PathBuf,
+}
+
+pub struct Response {
+    pub status: u16,
+    body: Body,
+}
+
+enum Framing {
+    Length(usize),
+    Chunked { remaining: usize, done: bool },
+    Close,
+}
+
+/// The response body, decoded from whichever framing the server used.
+pub struct Body {
+    reader: BufReader<UnixStream>,
+    framing: Framing,
+}
+
+impl Client {
+    /// Uses the given host, or `DOCKER_HOST`, when it points at a unix
+    /// socket, otherwise the default socket path.
+    pub fn from_host(host: Option<String>) -> Result<Self> {
+        match host.or_else(|| std::env::var("DOCKER_HOST").ok()) {
+            Some(host) if !host.is_empty() => match host.strip_prefix("unix://") {
+                Some(path) => Ok(Self {
+                    socket: PathBuf::from(path),
+                }),
+                None => Err(std::io::Error::new(
+                    std::io::ErrorKind::Unsupported,
+                    format!(
+                        "Only unix sockets are supported, the docker host is {}",
+                        host
+                    ),
+                )),
+            },
+            _ => Ok(Self {
+                socket: PathBuf::from("/var/run/docker.sock"),
+            }),
+        }
+    }
+
+    pub fn request(
+        &self,
+        method: &str,
+        path: &str,
+        content_type: Option<&str>,
+        body: &[u8],
+    ) -> Result<Response> {
+        let mut stream = UnixStream::connect(&self.socket)?;
+
+        let mut head = format!(
+            "{} {} HTTP/1.1\r\nHost: docker\r\nConnection: close\r\nContent-Length: {}\r\n",
+            method,
+            path,
+            body.len()
+        );
+        if let Some(content_type) = content_type {
+            head.push_str(&format!("Content-Type: {}\r\n", content_type));
+        }
+        head.push_str("\r\n");
+
+        stream.write_all(head.as_bytes())?;
+        stream.write_all(body)?;
+        stream.flush()?;
+
+        Response::read(BufReader::new(stream))
+    }
+
+    /// Sends a body as `write` produces it, with chunked transfer encoding,
+    /// so large bodies such as build contexts are never held in memory.
+    pub fn request_stream(
+        &self,
+        method: &str,
+        path: &str,
+        content_type: &str,
+        write: impl FnOnce(&mut dyn Write) -> Result<()>,
+    ) -> Result<Response> {
+        let mut stream = UnixStream::connect(&self.socket)?;
+
+        let head = format!(
+            "{} {} HTTP/1.1\r\nHost: docker\r\nConnection: close\r\nTransfer-Encoding: chunked\r\nContent-Type: {}\r\n\r\n",
+            method, path, content_type
+        );
+        stream.write_all(head.as_bytes())?;
+
+        let mut body = BufWriter::with_capacity(64 * 1024, Chunked(&mut stream));
+        write(&mut body)?;
+        body.into_inner().map_err(|e| e.into_error())?;
+
+        stream.write_all(b"0\r\n\r\n")?;
+        stream.flush()?;
+
+        Response::read(BufReader::new(stream))
+    }
+
+    pub fn get(&self, path: &str) -> Result<Response> {
+        self.request("GET", path, None, &[])
+    }
+
+    pub fn post_json(&self, path: &str, body: &Value) -> Result<Response> {
+        let body = serde_json::to_vec(body)?;
+
+        self.request("POST", path, Some("application/json"), &body)
+    }
+}
+
+impl Response {
+    fn read(mut reader: BufReader<UnixStream>) -> Result<Self> {
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+
+        let status = line
+            .split_whitespace()
+            .nth(1)
+            .and_then(|status| status.parse().ok())
+            .ok_or_else(|| invalid(format!("Invalid status line {:?}", line)))?;
+
+        let mut framing = Framing::Close;
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line)? == 0 {
+                break;
+            }
+
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+
+            if let Some((name, value)) = line.split_once(':') {
+                let value = value.trim();
+                match name.to_ascii_lowercase().as_str() {
+                    "content-length" => {
+                        let length = value
+                            .parse()
+                            .map_err(|_| invalid(format!("Invalid length {}", value)))?;
+                        framing = Framing::Length(length);
+                    }
+                    "transfer-encoding" if value.eq_ignore_ascii_case("chunked") => {
+                        framing = Framing::Chunked {
+                            remaining: 0,
+                            done: false,
+                        };
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(Self {
+            status,
+            body: Body { reader, framing },
+        })
+    }
+
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    pub fn body(self) -> Body {
+        self.body
+    }
+
+    pub fn json(self) -> Result<Value> {
+        let mut contents = vec![];
+        let mut body = self.body;
+        body.read_to_end(&mut contents)?;
+
+        if contents.is_empty() {
+            return Ok(Value::Null);
+        }
+
+        Ok(serde_json::from_slice(&contents)?)
+    }
+
+    /// The error message the API returned, for a response that failed.
+    pub fn error(self) -> std::io::Error {
+        let status = self.status;
+        let message = self
+            .json()
+            .ok()
+            .and_then(|body| body["message"].as_str().map(|m| m.to_string()))
+            .unwrap_or_else(|| format!("Docker API returned {}", status));
+
+        std::io::Error::other(message)
+    }
+}
+
+impl Read for Body {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        match &mut self.framing {
+            Framing::Close => self.reader.read(buf),
+            Framing::Length(remaining) => {
+                if *remaining == 0 {
+                    return Ok(0);
+                }
+
+                let max = buf.len().min(*remaining);
+                let read = self.reader.read(&mut buf[..max])?;
+                *remaining -= read;
+
+                Ok(read)
+            }
+            Framing::Chunked { remaining, done } => {
+                if *done {
+                    return Ok(0);
+                }
+
+                if *remaining == 0 {
+                    let mut line = String::new();
+                    self.reader.read_line(&mut line)?;
+                    if line.trim().is_empty() {
+                        // The CRLF that ends the previous chunk
+                        line.clear();
+                        self.reader.read_line(&mut line)?;
+                    }
+
+                    let size = line.trim().split(';').next().unwrap_or_default();
+                    *remaining = usize::from_str_radix(size, 16)
+                        .map_err(|_| invalid(format!("Invalid chunk size {:?}", line)))?;
+
+                    if *remaining == 0 {
+                        *done = true;
+                        return Ok(0);
+                    }
+                }
+
+                let max = buf.len().min(*remaining);
+                let read = self.reader.read(&mut buf[..max])?;
+                *remaining -= read;
+
+                Ok(read)
+            }
+        }
+    }
+}
+
+/// Writes everything it is given as one HTTP chunk.
+struct Chunked<W: Write>(W);
+
+impl<W: Write> Write for Chunked<W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        write!(self.0, "{:x}\r\n", buf.len())?;
+        self.0.write_all(buf)?;
+        self.0.write_all(b"\r\n")?;
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.0.flush()
+    }
+}
+
+/// Percent-encodes a query string value.
+pub fn encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn invalid(message: String) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message)
+}
+
+/// A fake Engine API on a temporary socket, for tests.
+#[cfg(test)]
+pub(crate) mod fake {
+    use super::Client;
+    use std::io::BufRead;
+    use std::io::BufReader;
+    use std::io::Write;
+    use std::os::unix::net::UnixListener;
+    use std::thread::JoinHandle;
+
+    /// A request the fake received: its request line and decoded body.
+    pub struct Request {
+        pub line: String,
+        pub body: Vec<u8>,
+    }
+
+    /// Answers `count` connections with whatever `respond` returns for each
+    /// request, and hands back the requests once done.
+    pub fn serve(
+        name: &str,
+        count: usize,
+        respond: impl Fn(&Request) -> Vec<u8> + Send + 'static,
+    ) -> (Client, JoinHandle<Vec<Request>>) {
+        let socket =
+            std::env::temp_dir().join(format!("devcon-{}-{}.sock", name, std::process::id()));
+        let _ = std::fs::remove_file(&socket);
+        let listener = UnixListener::bind(&socket).unwrap();
+
+        let handle = std::thread::spawn(move || {
+            let mut requests = vec![];
+
+            for _ in 0..count {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                let request = read_request(&mut reader);
+
+                let mut stream = reader.into_inner();
+                stream.write_all(&respond(&request)).unwrap();
+                requests.push(request);
+            }
+
+            requests
+        });
+
+        (Client { socket }, handle)
+    }
+
+    fn read_request(reader: &mut impl BufRead) -> Request {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+
+        let mut length = 0;
+        let mut chunked = false;
+        loop {
+            let mut header = String::new();
+            reader.read_line(&mut header).unwrap();
+            let header = header.trim_end().to_ascii_lowercase();
+            if header.is_empty() {
+                break;
+            }
+
+            if let Some(value) = header.strip_prefix("content-length:") {
+                length = value.trim().parse().unwrap();
+            }
+            chunked |= header == "transfer-encoding: chunked";
+        }
+
+        let mut body = vec![];
+        if chunked {
+            loop {
+                let mut size = String::new();
+                reader.read_line(&mut size).unwrap();
+                let size = usize::from_str_radix(size.trim(), 16).unwrap();
+
+                let mut chunk = vec![0; size + 2];
+                reader.read_exact(&mut chunk).unwrap();
+                if size == 0 {
+                    break;
+                }
+                body.extend_from_slice(&chunk[..size]);
+            }
+        } else {
+            body.resize(length, 0);
+            reader.read_exact(&mut body).unwrap();
+        }
+
+        Request {
+            line: line.trim_end().to_string(),
+            body,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fake;
+
+    #[test]
+    fn reads_a_content_length_body() {
+        // Anything after the declared length is not part of the body
+        let (client, server) = fake::serve("length", 1, |_| {
+            b"HTTP/1.1 200 OK\r\nContent-Length: 11\r\n\r\n{\"Id\":\"ab\"}trailing".to_vec()
+        });
+
+        let response = client.get("/containers/json").unwrap();
+        assert!(response.is_success());
+        assert_eq!(response.json().unwrap()["Id"], "ab");
+
+        let requests = server.join().unwrap();
+        assert_eq!(requests[0].line, "GET /containers/json HTTP/1.1");
+    }
+
+    #[test]
+    fn reads_a_chunked_body() {
+        let (client, server) = fake::serve("chunked", 1, |_| {
+            b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\n{\"Id\"\r\n6;ext=1\r\n:\"ab\"}\r\n0\r\n\r\n"
+                .to_vec()
+        });
+
+        let response = client.get("/version").unwrap();
+        assert_eq!(response.json().unwrap()["Id"], "ab");
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn reports_api_errors() {
+        let (client, server) = fake::serve("error", 1, |_| {
+            b"HTTP/1.1 404 Not Found\r\nContent-Length: 22\r\n\r\n{\"message\":\"no such\"}\n"
+                .to_vec()
+        });
+
+        let response = client.get("/containers/x/json").unwrap();
+        assert_eq!(response.status, 404);
+        assert_eq!(response.error().to_string(), "no such");
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn streams_a_chunked_request_body() {
+        let (client, server) = fake::serve("stream", 1, |_| {
+            b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n".to_vec()
+        });
+
+        let body = vec![7u8; 200 * 1024];
+        let response = client
+            .request_stream("POST", "/build", "application/x-tar", |writer| {
+                writer.write_all(&body[..100])?;
+                writer.write_all(&body[100..])
+            })
+            .unwrap();
+        assert!(response.is_success());
+
+        let requests = server.join().unwrap();
+        assert_eq!(requests[0].body, body);
+    }
+}
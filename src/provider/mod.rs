@@ -1,32 +1,363 @@
-pub(crate) mod docker;
-pub(crate) mod docker_compose;
-pub(crate) mod podman;
-pub(crate) mod podman_compose;
+pub(crate) mod compose;
+pub(crate) mod compose_file;
+pub(crate) mod compose_override;
+pub(crate) mod container;
+pub(crate) mod detect;
+#[cfg(unix)]
+pub(crate) mod docker_api;
+pub(crate) mod dockerignore;
+pub(crate) mod engine;
+#[cfg(unix)]
+pub(crate) mod http;
+pub(crate) mod pod;
+
+use engine::Engine;
 
 use colored::Colorize;
+use std::collections::BTreeMap;
 use std::io::Result;
+use std::io::Write;
+use std::process::Stdio;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+
+static VERBOSE: AtomicBool = AtomicBool::new(false);
+static JSON: AtomicBool = AtomicBool::new(false);
+
+/// The image label holding devcontainer metadata, shared with other tools.
+pub(crate) const METADATA_LABEL: &str = "devcontainer.metadata";
+
+/// The container label holding the hash of its devcontainer.json, which
+/// every container devcon creates carries.
+pub(crate) const CONFIG_HASH_LABEL: &str = "devcon.config_hash";
+/// The container label holding the hash of its build inputs.
+pub(crate) const INPUTS_HASH_LABEL: &str = "devcon.inputs_hash";
+pub(crate) const LOCAL_FOLDER_LABEL: &str = "devcontainer.local_folder";
+pub(crate) const CONFIG_FILE_LABEL: &str = "devcontainer.config_file";
+/// The container label naming how devcon runs it, like `podman pod`.
+pub(crate) const PROVIDER_LABEL: &str = "devcon.provider";
+
+/// What containers run instead of a shell, so they stay up with no terminal
+/// attached and still stop promptly.
+pub(crate) const KEEP_ALIVE: &str = "trap 'exit 0' TERM; while sleep 1000 & wait $!; do :; done";
+
+/// A command to run in the container as the remote user.
+#[derive(Debug, Default)]
+pub(crate) struct Exec {
+    pub args: Vec<String>,
+    pub env: BTreeMap<String, String>,
+    /// Keeps stdin open for the command.
+    pub interactive: bool,
+    /// Allocates a terminal, for when devcon itself runs in one.
+    pub tty: bool,
+}
+
+impl Exec {
+    pub fn shell(command: String) -> Self {
+        Self {
+            args: vec!["sh".to_string(), "-c".to_string(), command],
+            ..Default::default()
+        }
+    }
+}
+
+/// Which logs to show.
+#[derive(Debug, Default)]
+pub(crate) struct Logs {
+    /// A compose service, or every service when `None`.
+    pub service: Option<String>,
+    /// Keeps printing new output until interrupted.
+    pub follow: bool,
+    /// Only output since a timestamp or a duration like `10m`.
+    pub since: Option<String>,
+}
+
+impl Logs {
+    /// The engine CLI flags for these options.
+    pub fn args(&self) -> Vec<String> {
+        let mut args = vec![];
+
+        if self.follow {
+            args.push("--follow".to_string());
+        }
+
+        if let Some(since) = &self.since {
+            args.push("--since".to_string());
+            args.push(since.clone());
+        }
+
+        args
+    }
+}
+
+/// Builds an `exec` for the engine CLI against a container.
+pub(crate) fn exec_command(
+    engine: &Engine,
+    container: &str,
+    user: &str,
+    workspace_folder: &str,
+    exec: &Exec,
+) -> std::process::Command {
+    let mut command = engine.command();
+    command.arg("exec");
+
+    if exec.interactive {
+        command.arg("-i");
+    }
+
+    if exec.tty {
+        command.arg("-t");
+    }
+
+    for (key, value) in &exec.env {
+        command.arg("-e").arg(format!("{}={}", key, value));
+    }
+
+    command
+        .arg("-u")
+        .arg(user)
+        .arg("-w")
+        .arg(workspace_folder)
+        .arg(container)
+        .args(&exec.args);
+
+    command
+}
+
+/// The exit code of a finished command, following the shell's convention
+/// for commands killed by a signal.
+pub(crate) fn exit_code(status: std::process::ExitStatus) -> i32 {
+    #[cfg(unix)]
+    if let Some(signal) = std::os::unix::process::ExitStatusExt::signal(&status) {
+        return 128 + signal;
+    }
+
+    status.code().unwrap_or(1)
+}
+
+/// Labels every container devcon creates carries, so its containers are
+/// found by exact value rather than by name.
+#[derive(Debug, Clone)]
+pub(crate) struct Labels {
+    /// The project directory, named like the spec's label.
+    pub local_folder: String,
+    /// The devcontainer.json path, named like the spec's label.
+    pub config_file: String,
+    /// A hash of the devcontainer.json contents.
+    pub config_hash: String,
+    /// A hash of everything the container was built from. Only computed
+    /// when a container is created, as it reads the whole build context.
+    pub inputs_hash: Option<String>,
+    pub provider: String,
+}
+
+impl Labels {
+    pub fn all(&self) -> Vec<(&str, &str)> {
+        let mut labels = vec![
+            (LOCAL_FOLDER_LABEL, self.local_folder.as_str()),
+            (CONFIG_FILE_LABEL, self.config_file.as_str()),
+            (CONFIG_HASH_LABEL, self.config_hash.as_str()),
+            (PROVIDER_LABEL, self.provider.as_str()),
+        ];
+        if let Some(hash) = &self.inputs_hash {
+            labels.push((INPUTS_HASH_LABEL, hash));
+        }
+
+        labels
+    }
+
+    /// Keeps the hashes an existing container was created with, so the labels
+    /// rendered for it stay the same until it is created again. Compose
+    /// recreates a service whose labels changed.
+    pub fn keep_hashes(&mut self, config_hash: Option<String>, inputs_hash: Option<String>) {
+        if let Some(config_hash) = config_hash {
+            self.config_hash = config_hash;
+        }
+        self.inputs_hash = inputs_hash;
+    }
+
+    /// The `key=value` label filters matching this project's containers. The
+    /// hashes are left out, so a container is still found after its config is
+    /// edited.
+    pub fn filters(&self) -> Vec<String> {
+        vec![
+            format!("{}={}", LOCAL_FOLDER_LABEL, self.local_folder),
+            format!("{}={}", CONFIG_FILE_LABEL, self.config_file),
+        ]
+    }
+}
 
 pub(crate) trait Provider {
     fn build(&self, use_cache: bool) -> Result<bool>;
     fn create(&self, args: Vec<String>) -> Result<bool>;
     fn start(&self) -> Result<bool>;
     fn stop(&self) -> Result<bool>;
-    fn restart(&self) -> Result<bool>;
-    fn attach(&self) -> Result<bool>;
+    fn stop_container(&self) -> Result<bool>;
     fn rm(&self) -> Result<bool>;
+    /// Removes the containers, along with their volumes and the images devcon
+    /// built when asked to.
+    fn down(&self, volumes: bool, images: bool) -> Result<bool>;
     fn exists(&self) -> Result<bool>;
     fn running(&self) -> Result<bool>;
+    /// The id of the primary container, if it exists.
+    fn id(&self) -> Result<Option<String>>;
     fn cp(&self, source: String, destination: String) -> Result<bool>;
-    fn exec(&self, cmd: String) -> Result<bool>;
+    /// Runs a command and returns its exit code.
+    fn exec(&self, exec: &Exec) -> Result<i32>;
+    /// Runs a command and returns what it printed.
+    fn exec_output(&self, exec: &Exec) -> Result<String>;
+    /// Prints the container's logs, or the services' for compose projects.
+    fn logs(&self, logs: &Logs) -> Result<bool>;
+    fn metadata(&self) -> Result<Option<String>>;
+    fn label(&self, metadata: String) -> Result<bool>;
+    fn container_label(&self, label: &str) -> Result<Option<String>>;
+    fn sync(&self) -> Result<bool>;
 }
 
-pub(crate) fn print_command(command: &std::process::Command) {
+/// Rebuilds an image on top of itself with the merged metadata label,
+/// since labels cannot be added to an existing image.
+pub(crate) fn relabel(engine: &Engine, tag: &str, metadata: &str) -> Result<bool> {
+    let mut command = engine.command();
+    command
+        .arg("build")
+        .arg("--label")
+        .arg(format!("{}={}", METADATA_LABEL, metadata))
+        .arg("-t")
+        .arg(tag)
+        .arg("-")
+        .stdin(Stdio::piped());
+
+    print_command(&mut command);
+
+    let mut child = command.spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(format!("FROM {}\n", tag).as_bytes())?;
+    }
+
+    Ok(child.wait()?.success())
+}
+
+pub(crate) fn inspect_label(
+    command: &mut std::process::Command,
+    label: &str,
+) -> Result<Option<String>> {
+    command
+        .arg("--format")
+        .arg(format!("{{{{ index .Config.Labels \"{}\" }}}}", label));
+
+    let output = command.output()?;
+    if !output.status.success() {
+        return Ok(None);
+    }
+
+    let value = String::from_utf8(output.stdout).unwrap().trim().to_string();
+    if value.is_empty() || value == "<no value>" {
+        Ok(None)
+    } else {
+        Ok(Some(value))
+    }
+}
+
+/// Adds `--filter` arguments matching this project's containers.
+pub(crate) fn filter_labels<'a>(
+    command: &'a mut std::process::Command,
+    labels: &Labels,
+) -> &'a mut std::process::Command {
+    for filter in labels.filters() {
+        command.arg("--filter").arg(format!("label={}", filter));
+    }
+
+    command
+}
+
+/// Inspects every container devcon created on the engine, whichever project
+/// it belongs to.
+pub(crate) fn inspect_all(engine: &Engine) -> Result<Vec<serde_json::Value>> {
+    let mut command = engine.command();
+    command
+        .arg("ps")
+        .arg("-aq")
+        .arg("--filter")
+        .arg(format!("label={}", CONFIG_HASH_LABEL));
+
+    let output = command.output()?;
+    if !output.status.success() {
+        let message = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(std::io::Error::other(message));
+    }
+
+    let ids: Vec<String> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|id| id.to_string())
+        .collect();
+    if ids.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let mut command = engine.command();
+    let output = command.arg("inspect").args(ids).output()?;
+
+    Ok(serde_json::from_slice(&output.stdout)?)
+}
+
+/// Prints a command before it runs. With `--json`, the command's output goes
+/// to stderr as well, so stdout only holds the result.
+pub(crate) fn print_command(command: &mut std::process::Command) {
+    if json() {
+        command.stdout(std::io::stderr());
+    }
+
     let exec = command.get_program();
+    let envs: Vec<String> = command
+        .get_envs()
+        .filter_map(|(key, value)| Some(format!("{}={} ", key.to_str()?, value?.to_str()?)))
+        .collect();
     let args: Vec<&str> = command
         .get_args()
         .map(|arg| arg.to_str().unwrap())
         .collect();
 
-    let output = format!("{} {}", exec.to_str().unwrap(), args.join(" "));
-    println!("{}", output.bold().blue());
+    let output = format!(
+        "{}{} {}",
+        envs.join(""),
+        exec.to_str().unwrap(),
+        args.join(" ")
+    );
+    let _ = writeln!(progress(), "{}", output.bold().blue());
+}
+
+pub(crate) fn print_request(method: &str, path: &str) {
+    let output = format!("{} {}", method, path);
+    let _ = writeln!(progress(), "{}", output.bold().blue());
+}
+
+pub(crate) fn set_json(json: bool) {
+    JSON.store(json, Ordering::Relaxed);
+}
+
+/// Whether stdout is kept for the result of a `--json` command.
+pub(crate) fn json() -> bool {
+    JSON.load(Ordering::Relaxed)
+}
+
+/// Where engine output and progress go: stdout, unless it is kept for the
+/// result of a `--json` command.
+pub(crate) fn progress() -> Box<dyn Write> {
+    if json() {
+        Box::new(std::io::stderr())
+    } else {
+        Box::new(std::io::stdout())
+    }
+}
+
+pub(crate) fn set_verbose(verbose: bool) {
+    VERBOSE.store(verbose, Ordering::Relaxed);
+}
+
+/// Prints details that are only shown with `--verbose`, to stderr so they
+/// don't mix with a command's output.
+pub(crate) fn print_verbose(message: &str) {
+    if VERBOSE.load(Ordering::Relaxed) {
+        eprintln!("{}", message.dimmed());
+    }
 }
@@ -0,0 +1,468 @@
+use std::collections::HashMap;
+use std::env;
+use std::io::Result;
+use std::process::Stdio;
+
+use super::compose_file;
+use super::compose_file::Service;
+use super::engine::Engine;
+use super::exec_command;
+use super::exit_code;
+use super::filter_labels;
+use super::inspect_label;
+use super::print_command;
+use super::relabel;
+use super::Exec;
+use super::Labels;
+use super::Logs;
+use super::Provider;
+use super::KEEP_ALIVE;
+use super::METADATA_LABEL;
+
+/// Runs compose projects as a native podman pod, for machines without
+/// podman-compose. Every service becomes a container in one pod, sharing its
+/// network namespace.
+#[derive(Debug)]
+pub struct Pod {
+    pub build_args: HashMap<String, String>,
+    pub cap_add: Vec<String>,
+    pub container_user: Option<String>,
+    pub engine: Engine,
+    pub env: HashMap<String, String>,
+    pub files: Vec<String>,
+    pub forward_ports: Vec<u16>,
+    pub labels: Labels,
+    pub mounts: Vec<String>,
+    pub name: String,
+    pub override_command: bool,
+    pub run_services: Vec<String>,
+    pub security_opt: Vec<String>,
+    pub service: String,
+    pub user: String,
+    pub workspace_folder: String,
+}
+
+impl Pod {
+    fn services(&self) -> Result<Vec<Service>> {
+        let services = compose_file::load(&self.files, &self.name)?;
+
+        Ok(compose_file::ordered(&services, &self.run_services))
+    }
+
+    fn container_name(&self, service: &str) -> String {
+        format!("{}-{}", &self.name, service)
+    }
+
+    fn image(&self, service: &Service) -> Option<String> {
+        if service.build.is_some() {
+            Some(format!("devcon/{}-{}", &self.name, &service.name))
+        } else {
+            service.image.clone()
+        }
+    }
+
+    fn create_container(&self, service: &Service) -> Result<bool> {
+        let image = match self.image(service) {
+            Some(image) => image,
+            None => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("Service {} has no image or build", &service.name),
+                ))
+            }
+        };
+
+        let primary = service.name == self.service;
+
+        let mut command = self.engine.command();
+        command
+            .arg("create")
+            .arg("--pod")
+            .arg(&self.name)
+            .arg("--name")
+            .arg(self.container_name(&service.name));
+        command.args(self.engine.pod_container_args());
+
+        // Starting a container starts the containers it requires first
+        if !service.depends_on.is_empty() {
+            let requires: Vec<String> = service
+                .depends_on
+                .iter()
+                .map(|dependency| self.container_name(dependency))
+                .collect();
+            command.arg("--requires").arg(requires.join(","));
+        }
+
+        for (key, value) in &service.environment {
+            command.arg("--env").arg(format!("{}={}", key, value));
+        }
+
+        for volume in &service.volumes {
+            command.arg("--mount").arg(volume);
+        }
+
+        for capability in &service.cap_add {
+            command.arg("--cap-add").arg(capability);
+        }
+
+        for option in &service.security_opt {
+            command.arg("--security-opt").arg(option);
+        }
+
+        let mut user = service.user.clone();
+        let mut service_command = service.command.clone();
+
+        if primary {
+            // Forwards the ssh-agent to the container
+            if let Ok(ssh_auth_sock) = env::var("SSH_AUTH_SOCK") {
+                command.arg("--volume");
+                command.arg(format!("{}:/ssh-agent", ssh_auth_sock));
+                command.arg("--env");
+                command.arg("SSH_AUTH_SOCK=/ssh-agent");
+            }
+
+            for (key, value) in &self.env {
+                command.arg("--env").arg(format!("{}={}", key, value));
+            }
+
+            for mount in &self.mounts {
+                command.arg("--mount").arg(mount);
+            }
+
+            for capability in &self.cap_add {
+                command.arg("--cap-add").arg(capability);
+            }
+
+            for option in &self.security_opt {
+                command.arg("--security-opt").arg(option);
+            }
+
+            for (key, value) in self.labels.all() {
+                command.arg("--label").arg(format!("{}={}", key, value));
+            }
+
+            if self.container_user.is_some() {
+                user = self.container_user.clone();
+            }
+
+            if self.override_command {
+                service_command = Some(vec![
+                    "/bin/sh".to_string(),
+                    "-c".to_string(),
+                    KEEP_ALIVE.to_string(),
+                ]);
+            }
+        }
+
+        if let Some(user) = user {
+            command.arg("--user").arg(user);
+        }
+
+        if let Some(working_dir) = &service.working_dir {
+            command.arg("--workdir").arg(working_dir);
+        }
+
+        command.arg(image);
+
+        for arg in service_command.unwrap_or_default() {
+            command.arg(arg);
+        }
+
+        print_command(&mut command);
+
+        Ok(command.status()?.success())
+    }
+}
+
+impl Provider for Pod {
+    fn build(&self, use_cache: bool) -> Result<bool> {
+        for service in self.services()? {
+            let build = match &service.build {
+                Some(build) => build,
+                None => continue,
+            };
+
+            let mut command = self.engine.command();
+            command
+                .arg("build")
+                .arg("-t")
+                .arg(self.image(&service).unwrap());
+
+            if let Some(dockerfile) = &build.dockerfile {
+                command.arg("-f").arg(build.context.join(dockerfile));
+            }
+
+            if !use_cache {
+                command.arg("--no-cache");
+            }
+
+            for (key, value) in build.args.iter().chain(&self.build_args) {
+                command.arg("--build-arg").arg(format!("{}={}", key, value));
+            }
+
+            command.arg(&build.context);
+
+            print_command(&mut command);
+
+            if !command.status()?.success() {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+
+    fn create(&self, _args: Vec<String>) -> Result<bool> {
+        let services = self.services()?;
+
+        // Ports live on the pod since its containers share one network
+        let mut command = self.engine.command();
+        command
+            .arg("pod")
+            .arg("create")
+            .arg("--name")
+            .arg(&self.name)
+            .args(&self.engine.pod_args);
+
+        for (key, value) in self.labels.all() {
+            command.arg("--label").arg(format!("{}={}", key, value));
+        }
+
+        for port in &self.forward_ports {
+            command.arg("--publish").arg(format!("{}:{}", port, port));
+        }
+
+        for service in &services {
+            for port in &service.ports {
+                command.arg("--publish").arg(port);
+            }
+        }
+
+        print_command(&mut command);
+
+        if !command.status()?.success() {
+            return Ok(false);
+        }
+
+        for service in &services {
+            if !self.create_container(service)? {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+
+    fn start(&self) -> Result<bool> {
+        let mut command = self.engine.command();
+        command.arg("pod").arg("start").arg(&self.name);
+
+        print_command(&mut command);
+
+        Ok(command.status()?.success())
+    }
+
+    fn stop(&self) -> Result<bool> {
+        let mut command = self.engine.command();
+        command.arg("pod").arg("stop").arg(&self.name);
+
+        print_command(&mut command);
+
+        Ok(command.status()?.success())
+    }
+
+    fn stop_container(&self) -> Result<bool> {
+        let mut command = self.engine.command();
+        command.arg("stop").arg(self.container_name(&self.service));
+
+        print_command(&mut command);
+
+        Ok(command.status()?.success())
+    }
+
+    fn rm(&self) -> Result<bool> {
+        let mut command = self.engine.command();
+        command.arg("pod").arg("rm").arg("--force").arg(&self.name);
+
+        print_command(&mut command);
+
+        Ok(command.status()?.success())
+    }
+
+    fn down(&self, volumes: bool, images: bool) -> Result<bool> {
+        let services = self.services()?;
+
+        let mut success = self.rm()?;
+
+        if volumes {
+            let names: Vec<&str> = services
+                .iter()
+                .flat_map(|service| &service.volumes)
+                .filter(|volume| volume.starts_with("type=volume,"))
+                .filter_map(|volume| {
+                    volume
+                        .split(',')
+                        .find_map(|part| part.strip_prefix("source="))
+                })
+                .collect();
+
+            if !names.is_empty() {
+                let mut command = self.engine.command();
+                command.arg("volume").arg("rm").args(names);
+
+                print_command(&mut command);
+
+                success &= command.status()?.success();
+            }
+        }
+
+        if images {
+            let built: Vec<String> = services
+                .iter()
+                .filter(|service| service.build.is_some())
+                .filter_map(|service| self.image(service))
+                .collect();
+
+            if !built.is_empty() {
+                let mut command = self.engine.command();
+                command.arg("rmi").args(built);
+
+                print_command(&mut command);
+
+                success &= command.status()?.success();
+            }
+        }
+
+        Ok(success)
+    }
+
+    fn exists(&self) -> Result<bool> {
+        let mut command = self.engine.command();
+        command.arg("pod").arg("ps").arg("-q");
+
+        let output = filter_labels(&mut command, &self.labels).output()?.stdout;
+
+        let value = String::from_utf8(output).unwrap().trim().to_string();
+
+        Ok(!value.is_empty())
+    }
+
+    /// Only the primary service's container carries devcon's labels.
+    fn running(&self) -> Result<bool> {
+        let mut command = self.engine.command();
+        command.arg("ps").arg("-q");
+
+        let output = filter_labels(&mut command, &self.labels).output()?.stdout;
+
+        let value = String::from_utf8(output).unwrap().trim().to_string();
+
+        Ok(!value.is_empty())
+    }
+
+    fn id(&self) -> Result<Option<String>> {
+        let mut command = self.engine.command();
+        command.arg("ps").arg("-aq");
+
+        let output = filter_labels(&mut command, &self.labels).output()?.stdout;
+
+        Ok(String::from_utf8_lossy(&output)
+            .lines()
+            .next()
+            .map(|id| id.to_string()))
+    }
+
+    fn cp(&self, source: String, destination: String) -> Result<bool> {
+        let mut command = self.engine.command();
+        command.arg("cp").arg(source).arg(format!(
+            "{}:{}",
+            self.container_name(&self.service),
+            destination
+        ));
+
+        print_command(&mut command);
+
+        Ok(command.status()?.success())
+    }
+
+    fn exec(&self, exec: &Exec) -> Result<i32> {
+        let mut command = exec_command(
+            &self.engine,
+            &self.container_name(&self.service),
+            &self.user,
+            &self.workspace_folder,
+            exec,
+        );
+
+        print_command(&mut command);
+
+        Ok(exit_code(command.status()?))
+    }
+
+    fn exec_output(&self, exec: &Exec) -> Result<String> {
+        let output = exec_command(
+            &self.engine,
+            &self.container_name(&self.service),
+            &self.user,
+            &self.workspace_folder,
+            exec,
+        )
+        .stderr(Stdio::null())
+        .output()?;
+
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+
+    /// Podman interleaves the containers' output, `--names` prefixes each
+    /// line with its container.
+    fn logs(&self, logs: &Logs) -> Result<bool> {
+        let containers: Vec<String> = match &logs.service {
+            Some(service) => vec![self.container_name(service)],
+            None => self
+                .services()?
+                .iter()
+                .map(|service| self.container_name(&service.name))
+                .collect(),
+        };
+
+        let mut command = self.engine.command();
+        command
+            .arg("logs")
+            .arg("--names")
+            .args(logs.args())
+            .args(containers);
+
+        print_command(&mut command);
+
+        Ok(command.status()?.success())
+    }
+
+    fn metadata(&self) -> Result<Option<String>> {
+        self.container_label(METADATA_LABEL)
+    }
+
+    fn label(&self, metadata: String) -> Result<bool> {
+        // Only images devcon built are its own to retag
+        let service = self
+            .services()?
+            .into_iter()
+            .find(|s| s.name == self.service);
+        match service.filter(|service| service.build.is_some()) {
+            Some(service) => relabel(&self.engine, &self.image(&service).unwrap(), &metadata),
+            None => Ok(true),
+        }
+    }
+
+    fn container_label(&self, label: &str) -> Result<Option<String>> {
+        let mut command = self.engine.command();
+        command
+            .arg("container")
+            .arg("inspect")
+            .arg(self.container_name(&self.service));
+
+        inspect_label(&mut command, label)
+    }
+
+    fn sync(&self) -> Result<bool> {
+        Ok(true)
+    }
+}
@@ -1,18 +1,43 @@
 extern crate directories;
 use directories::ProjectDirs;
 use serde::Deserialize;
+use serde::Serialize;
+use std::path::PathBuf;
 
-#[derive(Debug, Deserialize)]
+use crate::error::DevconError;
+use crate::error::Result;
+
+#[derive(Debug, Default, Deserialize, Serialize, PartialEq, Clone)]
 #[serde(rename_all = "lowercase")]
 pub enum Provider {
+    /// Picks an installed engine when devcon runs.
+    #[default]
+    Auto,
     Docker,
     Podman,
+    Nerdctl,
+    /// Docker through the Engine API socket rather than the CLI.
+    #[serde(rename = "docker-api")]
+    DockerApi,
 }
 
-impl Default for Provider {
-    fn default() -> Self {
-        Self::Docker
-    }
+/// How compose projects are run with podman.
+#[derive(Debug, Default, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ComposeBackend {
+    #[default]
+    Compose,
+    Pod,
+}
+
+/// What `start` does when a container was built from older inputs.
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum StaleAction {
+    #[default]
+    Warn,
+    Prompt,
+    Rebuild,
 }
 
 #[derive(Debug, Default, Deserialize)]
@@ -20,22 +45,45 @@ pub struct Settings {
     pub dotfiles: Vec<String>,
     #[serde(default)]
     pub provider: Provider,
+    #[serde(default)]
+    pub compose_backend: ComposeBackend,
+    /// The docker context (or podman connection) to run containers on.
+    pub context: Option<String>,
+    /// The engine host to run containers on, like `ssh://buildbox`.
+    pub host: Option<String>,
+    #[serde(default)]
+    pub stale: StaleAction,
 }
 
 impl Settings {
-    pub fn load() -> Self {
-        if let Some(dirs) = ProjectDirs::from("com", "Big Refactor", "devcon") {
+    pub fn load() -> Result<Self> {
+        if let Some(dirs) = project_dirs() {
             let dir = dirs.config_dir();
             let file = dir.join("config.toml");
 
             if file.is_file() {
-                let contents = std::fs::read_to_string(file).unwrap();
-                toml::from_str(&contents).unwrap()
+                let contents = std::fs::read_to_string(&file)?;
+                toml::from_str(&contents).map_err(|e| DevconError::SettingsParse {
+                    file,
+                    message: e.to_string(),
+                })
             } else {
-                Self::default()
+                Ok(Self::default())
             }
         } else {
-            Self::default()
+            Ok(Self::default())
         }
     }
 }
+
+fn project_dirs() -> Option<ProjectDirs> {
+    ProjectDirs::from("com", "Big Refactor", "devcon")
+}
+
+/// Where devcon keeps generated files such as compose overrides.
+pub fn cache_dir() -> PathBuf {
+    match project_dirs() {
+        Some(dirs) => dirs.cache_dir().to_path_buf(),
+        None => std::env::temp_dir().join("devcon"),
+    }
+}
@@ -0,0 +1,100 @@
+use std::time::SystemTime;
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+/// Parses an RFC 3339 timestamp, as engines report start times, into
+/// seconds since the epoch.
+pub fn unix_time(timestamp: &str) -> Option<u64> {
+    let number =
+        |range: std::ops::Range<usize>| -> Option<i64> { timestamp.get(range)?.parse().ok() };
+
+    let (year, month, day) = (number(0..4)?, number(5..7)?, number(8..10)?);
+    let (hour, minute, second) = (number(11..13)?, number(14..16)?, number(17..19)?);
+
+    // Skips fractional seconds to find the offset
+    let zone = timestamp[19..].trim_start_matches(|c: char| c == '.' || c.is_ascii_digit());
+    let offset = match zone.as_bytes().first() {
+        Some(b'+') | Some(b'-') => {
+            let hours: i64 = zone.get(1..3)?.parse().ok()?;
+            let minutes: i64 = zone.get(4..6)?.parse().ok()?;
+            let sign = if zone.starts_with('-') { -1 } else { 1 };
+            sign * (hours * 3600 + minutes * 60)
+        }
+        _ => 0,
+    };
+
+    // Days since the epoch from a civil date, after Howard Hinnant
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146097 + day_of_era - 719468;
+
+    let seconds = days * 86400 + hour * 3600 + minute * 60 + second - offset;
+
+    u64::try_from(seconds).ok()
+}
+
+/// Parses a duration like `90s`, `10m` or `1h30m`, as the engines' `--since`
+/// accepts, into seconds.
+pub fn duration(value: &str) -> Option<u64> {
+    let mut seconds = 0;
+    let mut number = String::new();
+
+    for c in value.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+
+        let unit = match c {
+            'h' => 3600,
+            'm' => 60,
+            's' => 1,
+            _ => return None,
+        };
+        seconds += number.parse::<u64>().ok()? * unit;
+        number.clear();
+    }
+
+    (!value.is_empty() && number.is_empty()).then_some(seconds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::duration;
+    use super::unix_time;
+
+    #[test]
+    fn parses_timestamps() {
+        assert_eq!(unix_time("1970-01-01T00:00:00Z"), Some(0));
+        assert_eq!(unix_time("2024-01-02T03:04:05Z"), Some(1704164645));
+        assert_eq!(
+            unix_time("2024-01-02T05:34:05.123456789+02:30"),
+            Some(1704164645)
+        );
+        assert_eq!(unix_time("2024-01-01T23:04:05.5-04:00"), Some(1704164645));
+    }
+
+    #[test]
+    fn rejects_the_zero_time() {
+        // What engines report for containers that never started
+        assert_eq!(unix_time("0001-01-01T00:00:00Z"), None);
+        assert_eq!(unix_time(""), None);
+    }
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(duration("90s"), Some(90));
+        assert_eq!(duration("1h30m"), Some(5400));
+        assert_eq!(duration("10"), None);
+        assert_eq!(duration("5d"), None);
+        assert_eq!(duration(""), None);
+    }
+}